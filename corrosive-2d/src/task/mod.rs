@@ -1,7 +1,7 @@
 use crate::comp::camera2d::{ActiveCamera2D, Camera2D};
 use crate::comp::{Depth, Mesh2D, Position2D, Renderer2dData, RendererMeta2D};
 use crate::position2d_operations::Move2D;
-use corrosive_ecs_core::ecs_core::{Arch, LockedRef, Member, Ref, Reference, Res};
+use corrosive_ecs_core::ecs_core::{Arch, LockedRef, Member, Ref, Reference, Res, ResMut};
 use corrosive_ecs_core_macro::task;
 use corrosive_ecs_renderer_backend::comp::RenderGraph;
 use corrosive_ecs_renderer_backend::public_functions::{
@@ -60,7 +60,7 @@ impl RenderNode for Renderer2DNode {
 }
 
 #[task]
-pub fn start_2d_renderer(graph: Res<RenderGraph>, renderer2d_data: Res<Renderer2dData>) {
+pub fn start_2d_renderer(graph: ResMut<RenderGraph>, renderer2d_data: ResMut<Renderer2dData>) {
     let (render_pass_tx, render_pass_rx) = unbounded::<RenderPass>();
     let (end_tx, end_rx) = unbounded::<()>();
     renderer2d_data.f_write().data = Some((render_pass_rx, end_tx));
@@ -70,7 +70,7 @@ pub fn start_2d_renderer(graph: Res<RenderGraph>, renderer2d_data: Res<Renderer2
     }));
 }
 #[task]
-pub fn init_camera(active_camera: Res<ActiveCamera2D>) {
+pub fn init_camera(active_camera: ResMut<ActiveCamera2D>) {
     let mut lock = active_camera.f_write();
     lock.buffer = Some(create_buffer_init(
         "2d_camera_buffer",
@@ -278,7 +278,7 @@ pub fn update_position(
             Reference::Expired => pos.remove(p.0),
         }
     }
-    let lock = active_camera.f_write();
+    let lock = active_camera.f_read();
 
     if let Some(b) = &lock.buffer {
         if let Some(t) = &lock.data {
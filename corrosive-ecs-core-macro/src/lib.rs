@@ -5,10 +5,12 @@ mod component;
 mod task;
 
 /// Used to mark functions as tasks.
+/// `&mut dyn Trait` members of `Arch` inputs are replaced with `DynMut<dyn Trait>`.
+/// `exit!()` in the body triggers an `Exit` output that is added to the outputs of the task.
 #[proc_macro_attribute]
 pub fn task(_attr: TokenStream, item: TokenStream) -> TokenStream {
     //task_fn(attr, item)
-    item
+    task::task(item)
 }
 /// Used to schedule the engine.
 #[proc_macro]
@@ -31,6 +33,11 @@ pub fn state(_input: TokenStream) -> TokenStream {
 pub fn resource(_input: TokenStream) -> TokenStream {
     TokenStream::new()
 }
+/// Used to mark structs & enums as an event.
+#[proc_macro_derive(Event)]
+pub fn event(_input: TokenStream) -> TokenStream {
+    TokenStream::new()
+}
 /// Used to mark structs & enums as a TraitBound.
 #[proc_macro_attribute]
 pub fn trait_bound(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -1,3 +1,107 @@
+use corrosive_ecs_core::build::codegen::replace_exit_macro;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Error, FnArg, GenericArgument, ItemFn, PathArguments,
+    ReturnType, Type,
+};
+
+/// Expands a task.
+/// `&mut dyn Trait` members of `Arch` inputs are replaced with `DynMut<dyn Trait>`
+/// and `exit!()` is replaced with an `Exit` output.
+pub fn task(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let mut item = parse_macro_input!(item as ItemFn);
+    replace_trait_mut_members(&mut item);
+    if let Err(error) = add_exit_output(&mut item) {
+        return error.to_compile_error().into();
+    }
+    quote! {#item}.into()
+}
+
+/// Replaces `&mut dyn Trait` members of `Arch` inputs with `DynMut<dyn Trait>`, the type the archetype yields.
+fn replace_trait_mut_members(item: &mut ItemFn) {
+    for input in item.sig.inputs.iter_mut() {
+        if let FnArg::Typed(pat_type) = input {
+            if let Type::Path(type_path) = &mut *pat_type.ty {
+                if let Some(segment) = type_path.path.segments.last_mut() {
+                    if segment.ident != "Arch" {
+                        continue;
+                    }
+                    if let PathArguments::AngleBracketed(generic_args) = &mut segment.arguments {
+                        if let Some(GenericArgument::Type(members)) = generic_args.args.first_mut()
+                        {
+                            replace_trait_mut(members);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Replaces `exit!()` in the body of the task and adds the `Exit` it triggers as the last output.
+fn add_exit_output(item: &mut ItemFn) -> syn::Result<()> {
+    if !replace_exit_macro(&mut item.block.stmts) {
+        return Ok(());
+    }
+
+    let outputs: Vec<Type> = match &item.sig.output {
+        ReturnType::Default => Vec::new(),
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::Tuple(tuple) => tuple.elems.iter().cloned().collect(),
+            ty => {
+                return Err(Error::new_spanned(
+                    ty,
+                    "Tasks that use exit!() must return a tuple of outputs.",
+                ))
+            }
+        },
+    };
+    let names: Vec<syn::Ident> = (0..outputs.len())
+        .map(|i| quote::format_ident!("engine_output{}", i))
+        .collect();
+    let stmts = &item.block.stmts;
+
+    let body = if outputs.is_empty() {
+        quote! {(|| { #(#stmts)* })();}
+    } else {
+        quote! {let (#(#names,)*) = (|| -> (#(#outputs,)*) { #(#stmts)* })();}
+    };
+
+    item.sig.output = parse_quote! {-> (#(#outputs,)* corrosive_ecs_core::ecs_core::Exit,)};
+    item.block = parse_quote! {{
+        let mut engine_exit = corrosive_ecs_core::ecs_core::Exit::default();
+        #[allow(clippy::redundant_closure_call)]
+        #body
+        (#(#names,)* engine_exit,)
+    }};
+    Ok(())
+}
+
+fn replace_trait_mut(ty: &mut Type) {
+    match ty {
+        Type::Tuple(tuple) => tuple.elems.iter_mut().for_each(replace_trait_mut),
+        Type::Reference(reference) if reference.mutability.is_some() => {
+            if let Type::TraitObject(trait_object) = &*reference.elem {
+                let trait_object = trait_object.clone();
+                *ty = parse_quote! {corrosive_ecs_core::ecs_core::DynMut<'_, #trait_object>};
+            }
+        }
+        Type::Path(type_path) => {
+            if let Some(segment) = type_path.path.segments.last_mut() {
+                if segment.ident != "Option" {
+                    return;
+                }
+                if let PathArguments::AngleBracketed(generic_args) = &mut segment.arguments {
+                    if let Some(GenericArgument::Type(member)) = generic_args.args.first_mut() {
+                        replace_trait_mut(member);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
 pub mod task_macro {
     /*pub fn task_fn(
         _: proc_macro::TokenStream,
@@ -0,0 +1,36 @@
+//! Times frames of the engine generated for the test app, where `drift` iterates over an archetype of
+//! `PARTICLES` entities through the generated `Arch::iter` and `EngineArch::get_item` of its columns.
+//! Run with `cargo bench -p corrosive-ecs-core-test`.
+
+use corrosive_ecs_core_test::corrosive_engine::engine::Engine;
+use corrosive_ecs_core_test::task::PARTICLES;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// Frames timed per run, below the 20 frames after which `stop` exits the app.
+const FRAMES: usize = 10;
+const RUNS: u32 = 50;
+
+/// Steps a freshly set up engine for `FRAMES` frames and returns the time it took.
+fn run() -> Duration {
+    let engine = Engine::new();
+    engine.setup();
+    let start = Instant::now();
+    engine.step_n(FRAMES, 1.0 / 60.0);
+    let elapsed = start.elapsed();
+    black_box(&engine);
+    elapsed
+}
+
+fn main() {
+    run();
+    let total: Duration = (0..RUNS).map(|_| run()).sum();
+    let frame = total / (RUNS * FRAMES as u32);
+
+    println!(
+        "{} entities, average of {} runs of {} frames",
+        PARTICLES, RUNS, FRAMES
+    );
+    println!("{:<12} {:>10.3?}", "frame", frame);
+    println!("{:<12} {:>10.3?}", "entity", frame / PARTICLES as u32);
+}
@@ -0,0 +1,8 @@
+use corrosive_ecs_core::build::general_helper::create_engine;
+use std::env;
+
+pub fn main() {
+    let current_dir = env::current_dir().expect("Failed to get current directory");
+    env::set_var("CORROSIVE_APP_ROOT", &current_dir);
+    create_engine();
+}
@@ -0,0 +1,3 @@
+pub mod arch_types;
+pub mod auto_prelude;
+pub mod engine;
@@ -0,0 +1,27 @@
+use corrosive_ecs_core_macro::{Component, Resource};
+
+#[derive(Component, Debug)]
+pub struct Position {
+    pub x: f64,
+}
+#[derive(Component, Debug)]
+pub struct Velocity {
+    pub x: f64,
+}
+#[derive(Resource, Default, Debug)]
+pub struct Counter {
+    pub frames: u64,
+    pub distance: f64,
+}
+#[derive(Resource, Default, Debug)]
+pub struct FixedCounter {
+    pub steps: u64,
+}
+#[derive(Component, Debug)]
+pub struct Particle {
+    pub x: f64,
+}
+#[derive(Component, Debug)]
+pub struct Drift {
+    pub x: f64,
+}
@@ -0,0 +1,31 @@
+//! A small app, built from `main.rs`, that the tests and benchmarks step one frame at a time.
+#![allow(warnings)]
+
+pub mod comp;
+#[path = ".corrosive_engine/mod.rs"]
+pub mod corrosive_engine;
+pub mod task;
+
+#[cfg(test)]
+mod tests {
+    use crate::corrosive_engine::engine::Engine;
+
+    #[test]
+    fn step_runs_every_task_once() {
+        let engine = Engine::new();
+        engine.step_n(4, 0.5);
+
+        let counter = engine.r_Counter.f_read();
+        assert_eq!(counter.frames, 4);
+        assert_eq!(counter.distance, 6.0);
+        assert_eq!(engine.r_FixedCounter.f_read().steps, 8);
+    }
+
+    #[test]
+    fn step_stops_after_exit() {
+        let engine = Engine::new();
+        engine.step_n(30, 0.25);
+
+        assert_eq!(engine.r_Counter.f_read().frames, 20);
+    }
+}
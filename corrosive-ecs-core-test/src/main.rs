@@ -0,0 +1,19 @@
+#![allow(warnings)]
+
+use corrosive_ecs_core_macro::corrosive_engine_builder;
+use corrosive_ecs_core_test::corrosive_engine::engine::run_engine;
+
+corrosive_engine_builder!(
+    setup "spawn",
+    update "movement",
+    update "count" after "movement",
+    update "stop" after "count",
+    setup "spawn_particles",
+    update "drift",
+    fixed_update "fixed_count",
+    fixed_rate 4
+);
+
+fn main() {
+    run_engine()
+}
@@ -0,0 +1,59 @@
+use crate::comp::{Counter, Drift, FixedCounter, Particle, Position, Velocity};
+use corrosive_ecs_core::ecs_core::{Arch, DeltaTime, Locked, RArch, Res, ResMut};
+use corrosive_ecs_core_macro::task;
+
+#[task]
+pub fn spawn() -> (RArch<(Locked<Position>, Velocity)>,) {
+    let mut moving: RArch<(Locked<Position>, Velocity)> = RArch::default();
+    moving.add((Locked::new(Position { x: 0.0 }), Velocity { x: 1.0 }));
+    moving.add((Locked::new(Position { x: 0.0 }), Velocity { x: 2.0 }));
+    (moving,)
+}
+
+#[task]
+pub fn movement(moving: Arch<(&Locked<Position>, &Velocity)>, delta: DeltaTime) {
+    for (position, velocity) in moving.iter() {
+        position.f_write().x += velocity.x * delta;
+    }
+}
+
+#[task]
+pub fn count(positions: Arch<(&Locked<Position>,)>, counter: ResMut<Counter>) {
+    let mut counter = counter.f_write();
+    counter.frames += 1;
+    counter.distance = positions
+        .iter()
+        .map(|(position,)| position.f_read().x)
+        .sum();
+}
+
+#[task]
+pub fn fixed_count(fixed_counter: ResMut<FixedCounter>) {
+    fixed_counter.f_write().steps += 1;
+}
+
+#[task]
+pub fn stop(counter: Res<Counter>) {
+    if counter.f_read().frames >= 20 {
+        exit!();
+    }
+}
+
+/// Number of entities in the archetype of `Particle` and `Drift`.
+pub const PARTICLES: usize = 10_000;
+
+#[task]
+pub fn spawn_particles() -> (RArch<(Locked<Particle>, Drift)>,) {
+    let mut particles: RArch<(Locked<Particle>, Drift)> = RArch::default();
+    for i in 0..PARTICLES {
+        particles.add((Locked::new(Particle { x: 0.0 }), Drift { x: i as f64 }));
+    }
+    (particles,)
+}
+
+#[task]
+pub fn drift(particles: Arch<(&Locked<Particle>, &Drift)>, delta: DeltaTime) {
+    for (particle, drift) in particles.iter() {
+        particle.f_write().x += drift.x * delta;
+    }
+}
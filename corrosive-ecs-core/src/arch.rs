@@ -1,30 +1,47 @@
 /// Automatically implemented for archetype input wrappers.
-pub trait EngineArch<T> {
+/// Sync, so the items can be read from several threads by `Arch::par_iter`.
+pub trait EngineArch<T>: Sync {
     fn remove(&self, index: usize);
     fn len(&self) -> usize;
     fn get_item(&self, index: usize) -> Option<T>;
 }
 
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use std::marker::PhantomData;
+
 /// Used as input archetypes for tasks.
 /// Generic type T is the item type of the archetype.
 /// T must be a tuple.
-/// Arch can be iterated over by `.iter()` function.
+/// Generic type F filters the archetypes with `With<T>` and `Without<T>`, it can be a tuple of them.
+/// `Changed<T>` and `Added<T>` filter the entities by the change ticks of a `Locked`, `LockedRef` or `Member` component.
+/// Filters do not change the item type.
+/// Arch can be iterated over by `.iter()` function, or across threads by `.par_iter()`.
 ///
 /// Example:
-/// ```rust
+/// ```ignore
 /// #[task]
 /// pub fn render_2d(meta: Arch<(&dyn Mesh2D, &RendererMeta2D)>, renderer2d_data: Res<Renderer2dData>) {
 ///     //code
 ///     }
 /// }
+///
+/// #[task]
+/// pub fn follow(members: Arch<(&Member<PositionPixil>,), Without<PixilCamera>>) {
+///     //code
+/// }
 /// ```
-pub struct Arch<'a, T> {
+pub struct Arch<'a, T, F = ()> {
     pub arch: &'a dyn EngineArch<T>,
     pub index: usize,
+    filter: PhantomData<F>,
 }
-impl<'a, T> Arch<'a, T> {
+impl<'a, T, F> Arch<'a, T, F> {
     pub fn new(arch: &'a dyn EngineArch<T>) -> Self {
-        Arch { arch, index: 0 }
+        Arch {
+            arch,
+            index: 0,
+            filter: PhantomData,
+        }
     }
     /// Marks an entity for removal.
     pub fn remove(&self, index: usize) {
@@ -41,6 +58,36 @@ impl<'a, T> Arch<'a, T> {
             index: self.index,
         }
     }
+    /// Returns a parallel iterator for the archetype.
+    /// The entities are split across the threads of the rayon thread pool.
+    ///
+    /// Example:
+    /// ```ignore
+    /// #[task]
+    /// pub fn gravity(arch: Arch<(&Locked<Position>, &Velocity)>, delta_time: DeltaTime) {
+    ///     arch.par_iter().for_each(|(position, velocity)| {
+    ///         position.f_write().y += velocity.y * delta_time;
+    ///     });
+    /// }
+    /// ```
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = T> + '_
+    where
+        T: Send,
+    {
+        let arch = self.arch;
+        (self.index..arch.len()).into_par_iter().map(move |index| {
+            arch.get_item(index)
+                .expect("Index of arch is out of bounds")
+        })
+    }
+    /// Calls the function for every item of the archetype across the threads of the rayon thread pool.
+    pub fn par_for_each<OP>(&self, op: OP)
+    where
+        T: Send,
+        OP: Fn(T) + Sync + Send,
+    {
+        self.par_iter().for_each(op);
+    }
 }
 /// Iterator wrapper for arch.
 pub struct ArchIterator<'a, T> {
@@ -56,3 +103,14 @@ impl<'a, T> Iterator for ArchIterator<'a, T> {
         result
     }
 }
+
+/// Filter of `Arch`, only archetypes that have T are included.
+pub struct With<T>(PhantomData<T>);
+/// Filter of `Arch`, archetypes that have T are excluded.
+pub struct Without<T>(PhantomData<T>);
+/// Filter of `Arch`, only entities whose T was written to or added since the last run of the task are included.
+/// The first run includes every entity.
+pub struct Changed<T>(PhantomData<T>);
+/// Filter of `Arch`, only entities whose T was added since the last run of the task are included.
+/// The first run includes every entity.
+pub struct Added<T>(PhantomData<T>);
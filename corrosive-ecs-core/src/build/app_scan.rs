@@ -1,12 +1,15 @@
-use proc_macro2::{TokenStream, TokenTree};
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
 use quote::ToTokens;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::{BinaryHeap, HashMap};
 use std::str::FromStr;
 use std::{fmt, fs, io};
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseBuffer, ParseStream, Result};
 use syn::token::Paren;
-use syn::{Error, Ident, Lit, LitStr, Token};
+use syn::{
+    Error, ExprPath, GenericArgument, Ident, Lit, LitInt, LitStr, PathArguments, Token, Type,
+};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Eq, PartialEq, Clone)]
 pub enum LogicalOperator {
@@ -17,10 +20,23 @@ pub enum LogicalOperator {
 pub enum LogicalExpression {
     Signal(String),
     State(String, String),
+    /// Path of a function returning a bool and the resource or state passed to it by reference.
+    Condition(String, ConditionInput),
+    /// True every n-th time it is checked, starting with the first time.
+    Every(u64),
+    /// True once the elapsed time reaches the seconds.
+    AfterSeconds(String),
+    /// True until the task ran.
+    Once,
     Operator(LogicalOperator),
     Not(Box<LogicalExpression>),
     Grouped(Vec<LogicalExpression>),
 }
+#[derive(serde::Serialize, serde::Deserialize, Debug, Eq, PartialEq, Clone)]
+pub enum ConditionInput {
+    Resource(String),
+    State(String),
+}
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
 pub enum TaskType {
     Update,
@@ -28,6 +44,12 @@ pub enum TaskType {
     Sync,
     Long,
     Setup,
+    Shutdown,
+    Teardown,
+    /// Runs once when the state of the type becomes the value.
+    OnEnter(String, String),
+    /// Runs once when the state of the type stops being the value.
+    OnExit(String, String),
 }
 #[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Clone, Hash)]
 pub enum DependencyType {
@@ -157,6 +179,27 @@ impl DependencyGraph {
         }
     }
 
+    /// Returns the nodes in the order they must run, dependencies first.
+    pub fn execution_order(&self) -> core::result::Result<Vec<DependencyType>, &str> {
+        let mut sorted = self.topological_sort()?;
+        sorted.reverse();
+        Ok(sorted)
+    }
+
+    pub fn remove_node(&mut self, node: &DependencyType) {
+        if let Some(dependencies) = self.dependents.remove(node) {
+            for dependency in dependencies {
+                if let Some(degree) = self.in_degrees.get_mut(&dependency) {
+                    *degree -= 1;
+                }
+            }
+        }
+        self.in_degrees.remove(node);
+        for dependencies in self.dependents.values_mut() {
+            dependencies.retain(|x| x != node);
+        }
+    }
+
     pub fn get_task_leaves(&self, node: &DependencyType) -> Vec<&String> {
         let mut nodes: Vec<&String> = vec![];
         for node in &self.dependents[node] {
@@ -176,8 +219,23 @@ pub struct AppPackage {
     pub setup_dependency: DependencyGraph,
     pub runtime_dependency: DependencyGraph,
     pub sync_dependency: DependencyGraph,
+    #[serde(default = "DependencyGraph::new")]
+    pub shutdown_dependency: DependencyGraph,
+    #[serde(default = "DependencyGraph::new")]
+    pub teardown_dependency: DependencyGraph,
+    #[serde(default = "DependencyGraph::new")]
+    pub transition_dependency: DependencyGraph,
     pub tasks: HashMap<String, (TaskType, Option<LogicalExpression>)>,
     pub packages: Vec<String>,
+    /// Runs the engine without any package that requires a window.
+    #[serde(default)]
+    pub headless: bool,
+    /// Marks a package that can not run without a window, such as a renderer.
+    #[serde(default)]
+    pub requires_window: bool,
+    /// Number of fixed updates per second.
+    #[serde(default)]
+    pub fixed_rate: Option<f64>,
 }
 impl Default for AppPackage {
     fn default() -> Self {
@@ -187,8 +245,45 @@ impl Default for AppPackage {
             setup_dependency: DependencyGraph::new(),
             runtime_dependency: DependencyGraph::new(),
             sync_dependency: DependencyGraph::new(),
+            shutdown_dependency: DependencyGraph::new(),
+            teardown_dependency: DependencyGraph::new(),
+            transition_dependency: DependencyGraph::new(),
             tasks: HashMap::new(),
             packages: vec![],
+            headless: false,
+            requires_window: false,
+            fixed_rate: None,
+        }
+    }
+}
+
+impl AppPackage {
+    /// Replaces the types of the states and resources named in the tasks of the app package by `f`.
+    pub fn map_types(&mut self, f: &impl Fn(&str) -> String) {
+        for (task_type, condition) in self.tasks.values_mut() {
+            if let TaskType::OnEnter(state, _) | TaskType::OnExit(state, _) = task_type {
+                *state = f(state);
+            }
+            if let Some(condition) = condition {
+                condition.map_types(f);
+            }
+        }
+    }
+}
+
+impl LogicalExpression {
+    fn map_types(&mut self, f: &impl Fn(&str) -> String) {
+        match self {
+            LogicalExpression::State(t, _)
+            | LogicalExpression::Condition(_, ConditionInput::Resource(t))
+            | LogicalExpression::Condition(_, ConditionInput::State(t)) => *t = f(t),
+            LogicalExpression::Not(expression) => expression.map_types(f),
+            LogicalExpression::Grouped(expressions) => {
+                for expression in expressions {
+                    expression.map_types(f);
+                }
+            }
+            _ => {}
         }
     }
 }
@@ -235,10 +330,10 @@ impl Parse for LogicalExpression {
                     logical_expressions.push(LogicalExpression::Signal(signal.value()));
                 }
             }
-            if input.peek(Ident) {
+            if input.peek(Ident::peek_any) {
                 let mut tokens: TokenStream = TokenStream::new();
                 tokens.extend(Some(input.parse::<TokenTree>()?));
-                let t = tokens.to_string();
+                let first = tokens.to_string();
 
                 while !input.is_empty()
                     && !input.peek(Token![,])
@@ -249,13 +344,25 @@ impl Parse for LogicalExpression {
                     tokens.extend(Some(token_tree));
                 }
 
+                let mut trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+                let expression = match trees.pop() {
+                    Some(TokenTree::Group(group))
+                        if group.delimiter() == Delimiter::Parenthesis =>
+                    {
+                        parse_run_condition(trees.into_iter().collect(), group)?
+                    }
+                    _ if first == "once" && trees.is_empty() => LogicalExpression::Once,
+                    _ => LogicalExpression::State(
+                        state_type(&tokens).unwrap_or(first),
+                        tokens.to_string(),
+                    ),
+                };
+
                 if is_not {
-                    logical_expressions.push(LogicalExpression::Not(Box::from(
-                        LogicalExpression::State(t, tokens.to_string()),
-                    )));
+                    logical_expressions.push(LogicalExpression::Not(Box::from(expression)));
                     is_not = false;
                 } else {
-                    logical_expressions.push(LogicalExpression::State(t, tokens.to_string()));
+                    logical_expressions.push(expression);
                 }
             }
             if input.peek(Token![!]) {
@@ -276,6 +383,94 @@ impl Parse for LogicalExpression {
     }
 }
 
+/// Returns the state type of a state value, which is the path before the variant,
+/// like `GameState` for `GameState::Menu` or `crate::states::GameState` for `crate::states::GameState::Menu`.
+fn state_type(value: &TokenStream) -> Option<String> {
+    let path = syn::parse2::<ExprPath>(value.clone()).ok()?.path;
+    let segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<String>>();
+    match segments.split_last() {
+        Some((_, state)) if !state.is_empty() => Some(state.join("::")),
+        _ => None,
+    }
+}
+
+/// Checks that the `fixed_rate` of an app is a positive number of updates per second.
+fn parse_fixed_rate(rate: f64, span: proc_macro2::Span) -> Result<f64> {
+    if rate > 0.0 && rate.is_finite() {
+        Ok(rate)
+    } else {
+        Err(Error::new(
+            span,
+            "Number of fixed updates per second must be greater than 0.\nExample: (fixed_rate 60)",
+        ))
+    }
+}
+
+/// Parses a function call in the condition of a task.
+/// `every`, `after_seconds` and `once` are built in, any other path is called with the resource or state.
+fn parse_run_condition(path: TokenStream, arguments: Group) -> Result<LogicalExpression> {
+    match path.to_string().as_str() {
+        "every" => {
+            let frames: LitInt = syn::parse2(arguments.stream())?;
+            match frames.base10_parse::<u64>()? {
+                0 => Err(Error::new_spanned(
+                    frames,
+                    "Number of checks must be greater than 0.\nExample: if(every(10))",
+                )),
+                v => Ok(LogicalExpression::Every(v)),
+            }
+        }
+        "after_seconds" => {
+            let seconds: f64 = match syn::parse2::<Lit>(arguments.stream())? {
+                Lit::Float(v) => v.base10_parse()?,
+                Lit::Int(v) => v.base10_parse()?,
+                v => {
+                    return Err(Error::new_spanned(
+                        v,
+                        "Number of seconds.\nExample: if(after_seconds(2.5))",
+                    ))
+                }
+            };
+            Ok(LogicalExpression::AfterSeconds(format!("{:?}", seconds)))
+        }
+        "once" if arguments.stream().is_empty() => Ok(LogicalExpression::Once),
+        _ => {
+            let ty: Type = syn::parse2(arguments.stream())?;
+            if let Type::Path(type_path) = &ty {
+                if let Some(segment) = type_path.path.segments.last() {
+                    if let PathArguments::AngleBracketed(generic_args) = &segment.arguments {
+                        if let Some(GenericArgument::Type(inner_type)) = generic_args.args.first() {
+                            let inner_type =
+                                inner_type.to_token_stream().to_string().replace(" ", "");
+                            let path = path.to_string().replace(" ", "");
+                            if segment.ident == "Res" {
+                                return Ok(LogicalExpression::Condition(
+                                    path,
+                                    ConditionInput::Resource(inner_type),
+                                ));
+                            }
+                            if segment.ident == "State" {
+                                return Ok(LogicalExpression::Condition(
+                                    path,
+                                    ConditionInput::State(inner_type),
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+            Err(Error::new_spanned(
+                ty,
+                "Expected Res<T> or State<T> as the input of a run condition.\nExample: if(low_health(Res<Health>))",
+            ))
+        }
+    }
+}
+
 impl Parse for AppPackage {
     fn parse(input: ParseStream) -> Result<Self> {
         if input.is_empty() {
@@ -285,6 +480,9 @@ impl Parse for AppPackage {
             Runtime,
             Setup,
             Sync,
+            Shutdown,
+            Teardown,
+            Transition,
         }
 
         let mut app_package: AppPackage = AppPackage::default();
@@ -384,6 +582,70 @@ impl Parse for AppPackage {
                     ));
                 }
             },
+            "shutdown" => match input.parse::<Lit>() {
+                Ok(Lit::Str(T)) => {
+                    internal_task_type = InternalTaskType::Shutdown;
+                    task_name = Some((T.value(), TaskType::Shutdown))
+                }
+                T => {
+                    return Err(Error::new_spanned(
+                        match T {
+                            Ok(T) => T.to_token_stream(),
+                            Err(E) => E.into_compile_error(),
+                        },
+                        "String literal of name of a task.\nExample: (shutdown \"shutdown_task\")",
+                    ));
+                }
+            },
+            "teardown" => match input.parse::<Lit>() {
+                Ok(Lit::Str(T)) => {
+                    internal_task_type = InternalTaskType::Teardown;
+                    task_name = Some((T.value(), TaskType::Teardown))
+                }
+                T => {
+                    return Err(Error::new_spanned(
+                        match T {
+                            Ok(T) => T.to_token_stream(),
+                            Err(E) => E.into_compile_error(),
+                        },
+                        "String literal of name of a task.\nExample: (teardown \"teardown_task\")",
+                    ));
+                }
+            },
+            "on_enter" | "on_exit" => {
+                let mut tokens: TokenStream = TokenStream::new();
+                while !input.is_empty() && !input.peek(LitStr) {
+                    tokens.extend(Some(input.parse::<TokenTree>()?));
+                }
+                let Some(state) = state_type(&tokens) else {
+                    return Err(Error::new_spanned(
+                        tokens,
+                        "Value of a state.\nExample: (on_enter StateExample::A \"enter_task\")",
+                    ));
+                };
+                match input.parse::<Lit>() {
+                    Ok(Lit::Str(T)) => {
+                        internal_task_type = InternalTaskType::Transition;
+                        task_name = Some((
+                            T.value(),
+                            if ident == "on_enter" {
+                                TaskType::OnEnter(state, tokens.to_string())
+                            } else {
+                                TaskType::OnExit(state, tokens.to_string())
+                            },
+                        ))
+                    }
+                    T => {
+                        return Err(Error::new_spanned(
+                            match T {
+                                Ok(T) => T.to_token_stream(),
+                                Err(E) => E.into_compile_error(),
+                            },
+                            "String literal of name of a task.\nExample: (on_enter StateExample::A \"enter_task\")",
+                        ));
+                    }
+                }
+            }
             "group" => {
                 if input.peek(syn::Ident) {
                     match input.parse::<Ident>() {
@@ -550,10 +812,31 @@ impl Parse for AppPackage {
                     ));
                 }
             },
+            "headless" => app_package.headless = true,
+            "requires_window" => app_package.requires_window = true,
+            "fixed_rate" => match input.parse::<Lit>() {
+                Ok(Lit::Int(T)) => {
+                    app_package.fixed_rate =
+                        Some(parse_fixed_rate(T.base10_parse::<f64>()?, T.span())?)
+                }
+                Ok(Lit::Float(T)) => {
+                    app_package.fixed_rate =
+                        Some(parse_fixed_rate(T.base10_parse::<f64>()?, T.span())?)
+                }
+                T => {
+                    return Err(Error::new_spanned(
+                        match T {
+                            Ok(T) => T.to_token_stream(),
+                            Err(E) => E.into_compile_error(),
+                        },
+                        "Number of fixed updates per second.\nExample: (fixed_rate 60)",
+                    ));
+                }
+            },
             _ => {
                 return Err(Error::new_spanned(
                         ident,
-                        "Expected path, update, fixed_update, sync_update, long_update, setup, group or package."));
+                        "Expected path, update, fixed_update, sync_update, long_update, setup, shutdown, teardown, on_enter, on_exit, group, package, headless, requires_window or fixed_rate."));
             }
         }
 
@@ -695,6 +978,9 @@ impl Parse for AppPackage {
             sub.tasks.extend(app_package.tasks);
             sub.path = app_package.path;
             sub.name = app_package.name;
+            sub.headless |= app_package.headless;
+            sub.requires_window |= app_package.requires_window;
+            sub.fixed_rate = sub.fixed_rate.or(app_package.fixed_rate);
             app_package = sub;
         }
 
@@ -729,8 +1015,145 @@ impl Parse for AppPackage {
                         .add_dependency(dependency.0, dependency.1)
                 }
             }
+            InternalTaskType::Shutdown => {
+                for node in nodes {
+                    app_package.shutdown_dependency.add_node(node);
+                }
+                for dependency in dependencies {
+                    app_package
+                        .shutdown_dependency
+                        .add_dependency(dependency.0, dependency.1)
+                }
+            }
+            InternalTaskType::Teardown => {
+                for node in nodes {
+                    app_package.teardown_dependency.add_node(node);
+                }
+                for dependency in dependencies {
+                    app_package
+                        .teardown_dependency
+                        .add_dependency(dependency.0, dependency.1)
+                }
+            }
+            InternalTaskType::Transition => {
+                for node in nodes {
+                    app_package.transition_dependency.add_node(node);
+                }
+                for dependency in dependencies {
+                    app_package
+                        .transition_dependency
+                        .add_dependency(dependency.0, dependency.1)
+                }
+            }
         }
 
         Ok(app_package)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str) -> DependencyType {
+        DependencyType::Task(name.to_string())
+    }
+
+    fn position(order: &[DependencyType], name: &str) -> usize {
+        order.iter().position(|x| *x == task(name)).unwrap()
+    }
+
+    #[test]
+    fn dependencies_run_first() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(task("input"), task("movement"));
+        graph.add_dependency(task("movement"), task("render"));
+        graph.add_node(task("audio"));
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(order.len(), 4);
+        assert!(position(&order, "input") < position(&order, "movement"));
+        assert!(position(&order, "movement") < position(&order, "render"));
+    }
+
+    #[test]
+    fn cycles_are_detected() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(task("a"), task("b"));
+        graph.add_dependency(task("b"), task("a"));
+        assert!(graph.topological_sort().is_err());
+    }
+
+    #[test]
+    fn merge_keeps_dependencies_of_both_graphs() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(task("a"), task("b"));
+        let mut other = DependencyGraph::new();
+        other.add_dependency(task("b"), task("c"));
+        other.add_node(task("d"));
+        graph.merge(&other);
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(order.len(), 4);
+        assert!(position(&order, "a") < position(&order, "b"));
+        assert!(position(&order, "b") < position(&order, "c"));
+    }
+
+    #[test]
+    fn removed_nodes_leave_the_order() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency(task("a"), task("b"));
+        graph.add_dependency(task("b"), task("c"));
+        graph.remove_node(&task("b"));
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(order.len(), 2);
+        assert!(!order.contains(&task("b")));
+    }
+
+    #[test]
+    fn fixed_rate_must_be_positive() {
+        let app_package: AppPackage = syn::parse_str("update \"a\", fixed_rate 60").unwrap();
+        assert_eq!(app_package.fixed_rate, Some(60.0));
+        assert!(syn::parse_str::<AppPackage>("update \"a\", fixed_rate 0").is_err());
+        assert!(syn::parse_str::<AppPackage>("update \"a\", fixed_rate 0.0").is_err());
+    }
+
+    #[test]
+    fn transition_state_is_the_path_before_the_variant() {
+        let app_package: AppPackage = syn::parse_str(
+            "on_enter StateExample::A \"enter\", on_exit crate::states::GameState::Menu \"leave\"",
+        )
+        .unwrap();
+        match &app_package.tasks["enter"].0 {
+            TaskType::OnEnter(state, _) => assert_eq!(state, "StateExample"),
+            other => panic!("{:?}", other),
+        }
+        match &app_package.tasks["leave"].0 {
+            TaskType::OnExit(state, value) => {
+                assert_eq!(state, "crate::states::GameState");
+                assert_eq!(value.replace(' ', ""), "crate::states::GameState::Menu");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(syn::parse_str::<AppPackage>("on_enter Menu \"enter\"").is_err());
+    }
+
+    #[test]
+    fn condition_state_is_the_path_before_the_variant() {
+        let app_package: AppPackage =
+            syn::parse_str("update \"menu\" if(crate::states::GameState::Menu)").unwrap();
+        let Some(LogicalExpression::Grouped(expressions)) = &app_package.tasks["menu"].1 else {
+            panic!("{:?}", app_package.tasks["menu"].1);
+        };
+        match &expressions[..] {
+            [LogicalExpression::Grouped(inner)] => match &inner[..] {
+                [LogicalExpression::State(state, _)] => {
+                    assert_eq!(state, "crate::states::GameState")
+                }
+                other => panic!("{:?}", other),
+            },
+            other => panic!("{:?}", other),
+        }
+    }
+}
@@ -1,8 +1,12 @@
 use crate::build::app_scan::{
-    AppPackage, DependencyGraph, DependencyType, LogicalExpression, LogicalOperator, TaskType,
+    AppPackage, ConditionInput, DependencyGraph, DependencyType, LogicalExpression,
+    LogicalOperator, TaskType,
 };
 use crate::build::components_scan::ComponentMap;
-use crate::build::tasks_scan::{MemberType, Task, TaskInput, TaskMap, TaskOutput};
+use crate::build::error::{BuildError, BuildResult};
+use crate::build::schedule::{get_access_conflicts, order_writers, ScheduleDump};
+use crate::build::tasks_scan::{ArchFilter, MemberType, Task, TaskInput, TaskMap, TaskOutput};
+use crate::build::type_scan::ComponentNames;
 use proc_macro2::{Span, TokenStream};
 use quote::quote;
 use std::collections::{HashMap, HashSet};
@@ -13,7 +17,10 @@ use std::{io, vec};
 use syn::spanned::Spanned;
 use syn::token::Semi;
 use syn::visit_mut::{self, VisitMut};
-use syn::{parse2, parse_str, LitStr, Stmt};
+use syn::{parse2, parse_str, Expr, LitStr, Stmt};
+
+/// Resources the engine declares itself instead of taking them from task inputs.
+const ENGINE_RESOURCES: [&str; 2] = ["FixedTime", "TimeScale"];
 
 #[derive(Debug)]
 pub struct ArchTypes {
@@ -22,12 +29,18 @@ pub struct ArchTypes {
     resources: HashSet<String>,
     states: HashSet<String>,
     hierarchy: HashSet<String>,
+    events: HashSet<String>,
+    event_cursors: HashSet<String>,
+    last_run_ticks: HashSet<String>,
+    removed: HashSet<String>,
+    removed_cursors: HashSet<String>,
 }
 #[derive(Debug)]
 pub struct TaskArchType {
     arch_type_type: Vec<MemberType>,
     task_index: usize,
-    input_arch_type_indexes: Vec<(usize, Vec<usize>)>,
+    input_arch_type_indexes: Vec<(usize, Vec<Option<usize>>)>,
+    filters: Vec<ArchFilter>,
 }
 #[derive(Debug)]
 pub struct TasksInputOutput {
@@ -35,29 +48,44 @@ pub struct TasksInputOutput {
     output: Vec<usize>,
 }
 
+/// Dependency graphs of every schedule of the engine.
+pub struct Schedules {
+    pub setup: DependencyGraph,
+    pub sync: DependencyGraph,
+    pub runtime: DependencyGraph,
+    pub shutdown: DependencyGraph,
+    pub teardown: DependencyGraph,
+    pub transition: DependencyGraph,
+}
+
 pub fn create_app(
     app_packages: Vec<AppPackage>,
     task_maps: Vec<TaskMap>,
     trait_to_components: HashMap<String, HashSet<String>>,
-) -> (TokenStream, ArchTypes) {
+) -> BuildResult<(TokenStream, ArchTypes, ScheduleDump)> {
     let mut tasks: HashMap<&String, Task> = HashMap::new();
     let mut task_options: HashMap<&String, &(TaskType, Option<LogicalExpression>)> = HashMap::new();
+    let mut task_packages: HashMap<&String, &str> = HashMap::new();
     let mut setup_dependency_map: DependencyGraph = DependencyGraph::new();
     let mut sync_dependency_map: DependencyGraph = DependencyGraph::new();
     let mut runtime_dependency_map: DependencyGraph = DependencyGraph::new();
+    let mut shutdown_dependency_map: DependencyGraph = DependencyGraph::new();
+    let mut teardown_dependency_map: DependencyGraph = DependencyGraph::new();
+    let mut transition_dependency_map: DependencyGraph = DependencyGraph::new();
 
     {
         let mut all_tasks: HashMap<String, Task> = task_maps
             .into_iter()
             .flat_map(|task_map| task_map.get_all())
             .collect();
+        let excluded_packages = get_excluded_packages(&app_packages);
         let mut packages: Vec<&str> = vec!["main"];
         let mut index = 0;
 
         while index < packages.len() {
             let package = packages[index];
             for app_package in &app_packages {
-                if package == app_package.name {
+                if package == app_package.name && !excluded_packages.contains(package) {
                     for v in &app_package.packages {
                         if !packages.contains(&v.as_str()) {
                             packages.push(v.as_str());
@@ -79,34 +107,166 @@ pub fn create_app(
                     } else {
                         sync_dependency_map.merge(&app_package.sync_dependency);
                     }
-                    app_package.tasks.iter().for_each(|x| {
+
+                    if shutdown_dependency_map.dependents.is_empty() {
+                        shutdown_dependency_map = app_package.shutdown_dependency.clone();
+                    } else {
+                        shutdown_dependency_map.merge(&app_package.shutdown_dependency);
+                    }
+
+                    if teardown_dependency_map.dependents.is_empty() {
+                        teardown_dependency_map = app_package.teardown_dependency.clone();
+                    } else {
+                        teardown_dependency_map.merge(&app_package.teardown_dependency);
+                    }
+
+                    if transition_dependency_map.dependents.is_empty() {
+                        transition_dependency_map = app_package.transition_dependency.clone();
+                    } else {
+                        transition_dependency_map.merge(&app_package.transition_dependency);
+                    }
+                    for x in &app_package.tasks {
+                        let task = all_tasks.remove(x.0).ok_or_else(|| {
+                            BuildError::new(format!(
+                                "task \"{}\" of package \"{}\" is not defined, no function marked with #[task] has this name",
+                                x.0, app_package.name
+                            ))
+                        })?;
                         task_options.insert(x.0, x.1);
-                        tasks.insert(
-                            x.0,
-                            all_tasks
-                                .remove(x.0)
-                                .unwrap_or_else(|| panic!("Tasks {} not defined", x.0)),
-                        );
-                    });
+                        task_packages.insert(x.0, app_package.name.as_str());
+                        tasks.insert(x.0, task);
+                    }
                 }
             }
             index += 1;
         }
+
+        for app_package in &app_packages {
+            if excluded_packages.contains(app_package.name.as_str()) {
+                for task in app_package.tasks.keys() {
+                    let node = DependencyType::Task(task.clone());
+                    setup_dependency_map.remove_node(&node);
+                    runtime_dependency_map.remove_node(&node);
+                    sync_dependency_map.remove_node(&node);
+                    shutdown_dependency_map.remove_node(&node);
+                    teardown_dependency_map.remove_node(&node);
+                    transition_dependency_map.remove_node(&node);
+                }
+            }
+        }
+    }
+    order_writers(&mut setup_dependency_map, &tasks, &trait_to_components);
+    order_writers(&mut runtime_dependency_map, &tasks, &trait_to_components);
+    let schedules = [
+        ("setup", &setup_dependency_map),
+        ("sync", &sync_dependency_map),
+        ("runtime", &runtime_dependency_map),
+        ("shutdown", &shutdown_dependency_map),
+        ("teardown", &teardown_dependency_map),
+        ("transition", &transition_dependency_map),
+    ];
+    for (name, graph) in &schedules {
+        if graph.topological_sort().is_err() {
+            return Err(BuildError::new(format!(
+                "the {} schedule has a circular dependency, check the before, after and groups of its tasks",
+                name
+            )));
+        }
+    }
+    let mut schedule_dump = ScheduleDump::new(
+        &schedules,
+        &tasks,
+        &task_options,
+        &task_packages,
+        &trait_to_components,
+    );
+    for (name, graph) in [
+        ("setup", &setup_dependency_map),
+        ("runtime", &runtime_dependency_map),
+    ] {
+        schedule_dump.conflicts.extend(get_access_conflicts(
+            name,
+            graph,
+            &tasks,
+            &trait_to_components,
+        ));
     }
     let arch_types =
         get_all_archetypes(tasks.values().collect::<Vec<&Task>>(), trait_to_components);
+    let fixed_rate = app_packages
+        .iter()
+        .find(|x| x.name == "main")
+        .and_then(|x| x.fixed_rate);
 
-    (
+    Ok((
         generate_app_body(
             &tasks,
             &task_options,
-            &setup_dependency_map,
-            &sync_dependency_map,
-            &runtime_dependency_map,
+            &Schedules {
+                setup: setup_dependency_map,
+                sync: sync_dependency_map,
+                runtime: runtime_dependency_map,
+                shutdown: shutdown_dependency_map,
+                teardown: teardown_dependency_map,
+                transition: transition_dependency_map,
+            },
             &arch_types,
+            fixed_rate,
         ),
         arch_types,
-    )
+        schedule_dump,
+    ))
+}
+
+/// Returns the packages that must be left out of a headless engine.
+/// These are the packages that require a window and the packages that depend on their tasks.
+fn get_excluded_packages(app_packages: &Vec<AppPackage>) -> HashSet<&str> {
+    let mut excluded: HashSet<&str> = HashSet::new();
+    if !app_packages.iter().any(|x| x.name == "main" && x.headless) {
+        return excluded;
+    }
+
+    let mut owners: HashMap<&String, &str> = HashMap::new();
+    for app_package in app_packages {
+        for task in app_package.tasks.keys() {
+            owners.insert(task, app_package.name.as_str());
+        }
+        if app_package.requires_window {
+            excluded.insert(app_package.name.as_str());
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for app_package in app_packages {
+            if app_package.name == "main" || excluded.contains(app_package.name.as_str()) {
+                continue;
+            }
+            let depends_on_excluded = [
+                &app_package.setup_dependency,
+                &app_package.runtime_dependency,
+                &app_package.sync_dependency,
+                &app_package.shutdown_dependency,
+                &app_package.teardown_dependency,
+                &app_package.transition_dependency,
+            ]
+            .iter()
+            .flat_map(|x| x.in_degrees.keys())
+            .any(|node| match node {
+                DependencyType::Task(t) => {
+                    owners.get(t).is_some_and(|owner| excluded.contains(owner))
+                }
+                _ => false,
+            });
+            if depends_on_excluded {
+                excluded.insert(app_package.name.as_str());
+                changed = true;
+            }
+        }
+    }
+
+    excluded
 }
 
 pub fn write_rust_file(token_stream: TokenStream, path: &str) -> io::Result<()> {
@@ -128,6 +288,11 @@ pub fn get_all_archetypes(
         resources: Default::default(),
         states: Default::default(),
         hierarchy: Default::default(),
+        events: Default::default(),
+        event_cursors: Default::default(),
+        last_run_ticks: Default::default(),
+        removed: Default::default(),
+        removed_cursors: Default::default(),
     };
 
     for task in &tasks {
@@ -138,20 +303,38 @@ pub fn get_all_archetypes(
                     v.sort();
                     archetypes.arch_types.push(v)
                 }
+                TaskOutput::Events(v) => {
+                    archetypes.events.insert(v.clone());
+                }
                 _ => {}
             }
         }
         for input in &task.inputs {
             match input {
-                TaskInput::Resources(_, v) => {
+                TaskInput::Resources(_, v) | TaskInput::ResourcesMut(_, v) => {
                     archetypes.resources.insert(v.clone());
                 }
-                TaskInput::State(_, v) => {
+                TaskInput::State(_, v) | TaskInput::StateMut(_, v) => {
                     archetypes.states.insert(v.clone());
                 }
                 TaskInput::Hierarchy(_, v) => {
                     archetypes.hierarchy.insert(v.clone());
                 }
+                TaskInput::EventReader(name, v) => {
+                    archetypes.events.insert(v.clone());
+                    archetypes
+                        .event_cursors
+                        .insert(event_cursor_name(&task.name, name));
+                }
+                TaskInput::EventWriter(_, v) => {
+                    archetypes.events.insert(v.clone());
+                }
+                TaskInput::Removed(name, v) => {
+                    archetypes.removed.insert(v.clone());
+                    archetypes
+                        .removed_cursors
+                        .insert(removed_cursor_name(&task.name, name));
+                }
                 _ => {}
             }
         }
@@ -179,76 +362,23 @@ pub fn get_all_archetypes(
         let mut index: usize = 0;
 
         for input_arch in &task.inputs {
-            if let TaskInput::Arch(_, input_arch) = input_arch {
-                let value_lists: Vec<Vec<String>> = input_arch
-                    .iter()
-                    .map(|key| match key {
-                        MemberType::Normal(t) => {
-                            vec![t.clone()]
-                        }
-                        MemberType::Trait(t) => trait_to_components
-                            .get(t)
-                            .expect("All keys must be present in the HashMap")
-                            .iter()
-                            .map(|x| x.clone())
-                            .collect(),
-                    })
-                    .collect();
-
-                let mut combinations: Vec<Vec<&String>> = vec![vec![]];
-
-                for values in &value_lists {
-                    combinations = combinations
-                        .into_iter()
-                        .flat_map(|combo| {
-                            values.iter().map(move |value| {
-                                let mut new_combo = combo.clone();
-                                new_combo.push(value);
-                                new_combo
-                            })
-                        })
-                        .collect();
-                }
-
-                let mut index_data: Vec<(usize, Vec<usize>)> = Vec::new();
-
-                for combination in combinations {
-                    index_data.extend(
-                        archetypes
-                            .arch_types
-                            .iter()
-                            .enumerate()
-                            .filter_map(|(outer_index, sub_vec)| {
-                                if combination.iter().all(|b_elem| sub_vec.contains(b_elem)) {
-                                    Some((
-                                        outer_index,
-                                        combination
-                                            .iter()
-                                            .enumerate()
-                                            .filter_map(|a| {
-                                                if let Some(t) =
-                                                    sub_vec.iter().position(|i| &i == a.1)
-                                                {
-                                                    Some(t)
-                                                } else {
-                                                    None
-                                                }
-                                            })
-                                            .collect(),
-                                    ))
-                                } else {
-                                    None
-                                }
-                            })
-                            .collect::<Vec<_>>(),
-                    )
-                }
+            if let TaskInput::Arch(_, input_arch, filters) = input_arch {
+                let index_data = get_arch_indexes(
+                    &archetypes.arch_types,
+                    input_arch,
+                    filters,
+                    &trait_to_components,
+                );
 
                 new.input.push(TaskArchType {
                     arch_type_type: input_arch.clone(),
                     task_index: index,
                     input_arch_type_indexes: index_data,
+                    filters: filters.clone(),
                 });
+                if has_change_filter(filters) {
+                    archetypes.last_run_ticks.insert(last_run_name(&task.name));
+                }
                 index += 1;
             }
         }
@@ -258,14 +388,276 @@ pub fn get_all_archetypes(
     archetypes
 }
 
+/// Returns the archetypes an archetype input yields from, along with the position of each member in them.
+/// Each archetype is resolved once. A member that is a trait yields every implementor the archetype holds,
+/// an optional member yields `None` only when the archetype holds none of its components.
+/// At least one member must be present in an archetype.
+fn get_arch_indexes(
+    arch_types: &[Vec<String>],
+    members: &[MemberType],
+    filters: &[ArchFilter],
+    trait_to_components: &HashMap<String, HashSet<String>>,
+) -> Vec<(usize, Vec<Option<usize>>)> {
+    let member_components: Vec<(Vec<String>, bool)> = members
+        .iter()
+        .map(|member| {
+            (
+                get_member_components(member, trait_to_components),
+                matches!(member, MemberType::Optional(_)),
+            )
+        })
+        .collect();
+
+    let mut index_data: Vec<(usize, Vec<Option<usize>>)> = Vec::new();
+
+    'arch: for (outer_index, sub_vec) in arch_types.iter().enumerate() {
+        if !filters.iter().all(|filter| match filter {
+            ArchFilter::With(t) => has_component(sub_vec, t),
+            ArchFilter::Without(t) => !has_component(sub_vec, t),
+            ArchFilter::Changed(t) | ArchFilter::Added(t) => tracked_position(sub_vec, t).is_some(),
+        }) {
+            continue;
+        }
+
+        let mut combinations: Vec<Vec<Option<usize>>> = vec![vec![]];
+        for (components, is_optional) in &member_components {
+            let mut positions: Vec<Option<usize>> = components
+                .iter()
+                .filter_map(|component| member_position(sub_vec, component))
+                .map(Some)
+                .collect();
+            positions.sort();
+            positions.dedup();
+            if positions.is_empty() {
+                if !is_optional {
+                    continue 'arch;
+                }
+                positions.push(None);
+            }
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    positions.iter().map(move |position| {
+                        let mut new_combination = combination.clone();
+                        new_combination.push(*position);
+                        new_combination
+                    })
+                })
+                .collect();
+        }
+
+        for combination in combinations {
+            let data = (outer_index, combination);
+            if data.1.iter().any(Option::is_some) && !index_data.contains(&data) {
+                index_data.push(data);
+            }
+        }
+    }
+    index_data
+}
+
+/// Name of an engine field that holds a value of a type, like `r_Position` for `ResMut<Position>`.
+/// Components that share a name are written by path, so `::` is replaced as well.
+fn type_field_name(prefix: &str, ty: &str) -> String {
+    format!("{}_{}", prefix, ty)
+        .replace("::", "_")
+        .replace(['<', '>', ' '], "")
+}
+/// Name of the engine field holding the events of a type.
+fn event_channel_name(event: &str) -> String {
+    type_field_name("ev", event)
+}
+/// Name of the engine field holding the cursor of an event reader.
+fn event_cursor_name(task: &str, input: &str) -> String {
+    format!("evc_{}_{}", task, input)
+}
+/// Name of the engine field holding the removed entities of a component.
+fn removed_channel_name(component: &str) -> String {
+    type_field_name("rm", component)
+}
+/// Name of the engine field holding the cursor of a `Removed` input.
+fn removed_cursor_name(task: &str, input: &str) -> String {
+    format!("rmc_{}_{}", task, input)
+}
+/// Generates the code that records an entity in the `Removed` channels of a component,
+/// should some task read them.
+fn generate_removed_record(
+    arch_types: &ArchTypes,
+    member: &str,
+    entity: &TokenStream,
+) -> TokenStream {
+    let mut code: TokenStream = TokenStream::new();
+    for component in &arch_types.removed {
+        if is_component(member, component) {
+            let channel_name: TokenStream =
+                parse_str(removed_channel_name(component).as_str()).unwrap();
+            code.extend(quote! {#channel_name.send(#entity);});
+        }
+    }
+    code
+}
+/// Name of the generated struct holding the component columns of an archetype.
+fn arch_storage_name(index: usize) -> String {
+    format!("ArchStorage{}", index)
+}
+/// Name of the column of an archetype storage holding the member at the index.
+/// The index after the last component is the entity.
+fn column_name(arch_type: &[String], index: usize) -> String {
+    if index == arch_type.len() {
+        "entities".to_string()
+    } else {
+        format!("c{}", index)
+    }
+}
+/// Name of the engine field holding the state of an `every` or `once` condition of a task.
+fn run_condition_name(task: &str, index: usize) -> String {
+    format!("rc_{}_{}", task, index)
+}
+/// Name of the engine field holding whether the state of a transition task matched its value in the last frame.
+fn transition_name(task: &str) -> String {
+    format!("tr_{}", task)
+}
+/// Name of the engine field holding the tick of the last run of a task with change filters.
+fn last_run_name(task: &str) -> String {
+    format!("lr_{}", task)
+}
+/// Checks if the filters of an archetype input depend on change ticks.
+fn has_change_filter(filters: &[ArchFilter]) -> bool {
+    filters
+        .iter()
+        .any(|x| matches!(x, ArchFilter::Changed(_) | ArchFilter::Added(_)))
+}
+/// Checks if a member of an archetype is stored in a wrapper that tracks changes.
+fn is_tracked(member: &str) -> bool {
+    member.starts_with("Locked<")
+        || member.starts_with("LockedRef<")
+        || member.starts_with("Member<")
+}
+/// Returns the position of a component stored in a wrapper that tracks changes.
+/// The component can be given with or without its wrapper.
+fn tracked_position(arch_type: &[String], component: &str) -> Option<usize> {
+    arch_type.iter().position(|member| {
+        is_tracked(member)
+            && (member == component
+                || member
+                    .split_once('<')
+                    .and_then(|(_, x)| x.strip_suffix(">"))
+                    .is_some_and(|x| x == component))
+    })
+}
+
+/// Returns the position of a member in the storage of an archetype.
+/// `Entity` is stored after the components of every archetype.
+fn member_position(arch_type: &[String], member: &str) -> Option<usize> {
+    if member == "Entity" {
+        return Some(arch_type.len());
+    }
+    arch_type.iter().position(|i| i == member)
+}
+
+/// Checks if an archetype has a component.
+/// `Locked`, `LockedRef`, `Ref` and `Member` wrappers of the archetype are ignored,
+/// so `Pos` matches `Locked<Pos>` as well.
+fn has_component(arch_type: &[String], component: &str) -> bool {
+    arch_type
+        .iter()
+        .any(|member| is_component(member, component))
+}
+
+/// Checks if a member of an archetype is a component, with or without its wrapper.
+fn is_component(member: &str, component: &str) -> bool {
+    if member == component {
+        return true;
+    }
+    ["Locked<", "LockedRef<", "Ref<", "Member<"]
+        .iter()
+        .any(|wrapper| {
+            member
+                .strip_prefix(wrapper)
+                .and_then(|x| x.strip_suffix(">"))
+                .is_some_and(|x| x == component)
+        })
+}
+
+/// Returns the components a member of an archetype input can be.
+fn get_member_components(
+    member: &MemberType,
+    trait_to_components: &HashMap<String, HashSet<String>>,
+) -> Vec<String> {
+    match member {
+        MemberType::Normal(t) => vec![t.clone()],
+        MemberType::Trait(t) => trait_to_components
+            .get(t)
+            .expect("All keys must be present in the HashMap")
+            .iter()
+            .cloned()
+            .collect(),
+        MemberType::TraitMut(t) => trait_to_components
+            .get(t)
+            .expect("All keys must be present in the HashMap")
+            .iter()
+            .map(|component| format!("Locked<{}>", component))
+            .collect(),
+        MemberType::Optional(t) => get_member_components(t, trait_to_components),
+    }
+}
+
+/// Returns the type of member of an archetype input, as it is yielded by the archetype.
+fn get_member_type(member: &MemberType) -> TokenStream {
+    match member {
+        MemberType::Normal(t) => {
+            let t: TokenStream = parse_str(t).unwrap();
+            quote! {&'a #t}
+        }
+        MemberType::Trait(t) => {
+            let t: TokenStream = parse_str(t).unwrap();
+            quote! {&'a dyn #t}
+        }
+        MemberType::TraitMut(t) => {
+            let t: TokenStream = parse_str(t).unwrap();
+            quote! {DynMut<'a, dyn #t>}
+        }
+        MemberType::Optional(t) => {
+            let t = get_member_type(t);
+            quote! {Option<#t>}
+        }
+    }
+}
+
+/// Returns the code yielding a member of an archetype input from its component.
+fn get_member_value(member: &MemberType, component: TokenStream) -> TokenStream {
+    match member {
+        MemberType::TraitMut(t) => {
+            let t: TokenStream = parse_str(t).unwrap();
+            quote! {DynMut::<dyn #t>::new(#component.f_write(), |v| v, |v| v)}
+        }
+        MemberType::Optional(t) => {
+            let value = get_member_value(t, component);
+            let t = get_member_type(t);
+            quote! {Some(#value as #t)}
+        }
+        _ => quote! {&#component},
+    }
+}
+
+/// Writes the auto prelude, which brings the components and tasks of the app and its packages into scope.
+/// Components that share a name are left out, the engine writes them by path.
 pub fn generate_prelude(
     component_map: Vec<(ComponentMap, String)>,
     task_map: Vec<(TaskMap, String)>,
+    component_names: &ComponentNames,
 ) -> TokenStream {
     let mut code: TokenStream = TokenStream::new();
 
     for component in component_map {
-        let all_components: HashMap<String, String> = component.0.get_all();
+        let prefix = component.1.replace("-", "_");
+        let all_components = component
+            .0
+            .get_all_paths()
+            .into_iter()
+            .filter(|(name, path)| {
+                component_names.get(&format!("{}::{}", prefix, path)) == Some(name)
+            });
         if component.1 == "crate" {
             for component in all_components {
                 let name: TokenStream =
@@ -273,8 +665,8 @@ pub fn generate_prelude(
                 code.extend(quote!(pub use crate::#name;).into_iter());
             }
         } else {
-            let prefix: TokenStream = parse_str(component.1.replace("-", "_").as_str())
-                .expect("Failed to parse component map prefix");
+            let prefix: TokenStream =
+                parse_str(prefix.as_str()).expect("Failed to parse component map prefix");
             for component in all_components {
                 let name: TokenStream =
                     parse_str(component.1.as_str()).expect("Failed to parse component map");
@@ -305,7 +697,7 @@ pub fn generate_prelude(
     quote! {
         #code
         pub use crate::corrosive_engine::arch_types::*;
-        pub use corrosive_ecs_core::ecs_core::{State, Res, Arch, Locked, LockedRef, Ref, Member, Hierarchy};
+        pub use corrosive_ecs_core::ecs_core::{State, StateMut, Res, ResMut, Arch, Locked, LockedRef, Ref, Member, Hierarchy};
     }
 }
 
@@ -315,6 +707,7 @@ pub fn generate_arch_types(arch_types: &ArchTypes) -> TokenStream {
     for task in &arch_types.tasks {
         let exact_name = parse_str::<TokenStream>(format!("\"{}\"", &task.0).as_str()).unwrap();
         for input_arch_type in &task.1.input {
+            let task_arch_type = input_arch_type;
             let arch_type_name: TokenStream =
                 parse_str(format!("{}{}", &task.0, input_arch_type.task_index).as_str()).unwrap();
             let mut arch_type_type: TokenStream = TokenStream::new();
@@ -324,6 +717,10 @@ pub fn generate_arch_types(arch_types: &ArchTypes) -> TokenStream {
             let mut remove_fn: TokenStream = TokenStream::new();
             let mut iter_code: TokenStream = TokenStream::new();
             let mut life_time: TokenStream = TokenStream::new();
+            let mut rows_members: TokenStream = TokenStream::new();
+            let mut rows_init: TokenStream = TokenStream::new();
+            let mut rows_fn: TokenStream = TokenStream::new();
+            let tracked = has_change_filter(&task_arch_type.filters);
 
             let mut index: usize = 0;
 
@@ -335,82 +732,122 @@ pub fn generate_arch_types(arch_types: &ArchTypes) -> TokenStream {
             }
             for input_arch_type in &input_arch_type.input_arch_type_indexes {
                 let var_name: TokenStream = parse_str(format!("ve{}", index).as_str()).unwrap();
-                let mut var_types: TokenStream = TokenStream::new();
                 let var_remove_name: TokenStream =
                     parse_str(format!("rve{}", index).as_str()).unwrap();
+                let rows_name: TokenStream = parse_str(format!("rows{}", index).as_str()).unwrap();
                 let mut iter_types: TokenStream = TokenStream::new();
+                let (len_code, row_code) = if tracked {
+                    let mut conditions: Vec<TokenStream> = Vec::new();
+                    for filter in &task_arch_type.filters {
+                        let (t, tick) = match filter {
+                            ArchFilter::Changed(t) => (t, quote! {changed}),
+                            ArchFilter::Added(t) => (t, quote! {added}),
+                            _ => continue,
+                        };
+                        let position =
+                            tracked_position(&arch_types.arch_types[input_arch_type.0], t).expect(
+                                "Change filters only match archetypes that track the component",
+                            );
+                        let position: TokenStream =
+                            parse_str(format!("c{}", position).as_str()).unwrap();
+                        conditions
+                            .push(quote! {#var_name.#position[*row].ticks().#tick() > last_run});
+                    }
+                    rows_members.extend(quote! {#rows_name: Vec<usize>,});
+                    rows_init.extend(quote! {
+                        let #rows_name: Vec<usize> = (0..#var_name.len()).filter(|row| #(#conditions)&&*).collect();
+                    });
+                    rows_fn.extend(quote! {#rows_name,});
+                    (
+                        quote! {self.#rows_name.len()},
+                        quote! {let index = self.#rows_name[index];},
+                    )
+                } else {
+                    (quote! {self.#var_name.len()}, TokenStream::new())
+                };
 
-                for input_arch_type in &arch_types.arch_types[input_arch_type.0] {
-                    let val: TokenStream =
-                        parse_str(format!("{},", input_arch_type).as_str()).unwrap();
-                    var_types.extend(val);
-                }
-                for input_arch_type_index in &input_arch_type.1 {
-                    let val: TokenStream =
-                        parse_str(format!("{}", input_arch_type_index).as_str()).unwrap();
-                    iter_types.extend(quote! {&self.#var_name[index].#val,});
+                let storage_name: TokenStream =
+                    parse_str(arch_storage_name(input_arch_type.0).as_str()).unwrap();
+                for (input_arch_type_index, member) in
+                    input_arch_type.1.iter().zip(&task_arch_type.arch_type_type)
+                {
+                    match (input_arch_type_index, member) {
+                        (Some(i), _) => {
+                            let val: TokenStream = parse_str(
+                                column_name(&arch_types.arch_types[input_arch_type.0], *i).as_str(),
+                            )
+                            .unwrap();
+                            let value =
+                                get_member_value(member, quote! {self.#var_name.#val[index]});
+                            iter_types.extend(quote! {#value,});
+                        }
+                        (None, _) => iter_types.extend(quote! {None,}),
+                    }
                 }
                 iter_code.extend(quote! {
-                if index < self.#var_name.len() {
+                if index < #len_code {
+                    #row_code
                     return Some((#iter_types));
                 };
-                index -= self.#var_name.len();
+                index -= #len_code;
                             });
 
-                members.extend(quote! {#var_name: &'a Vec<(#var_types)>,});
+                members.extend(quote! {#var_name: &'a #storage_name,});
                 members.extend(quote! {#var_remove_name: &'a RwLock<HashSet<usize>>,});
                 //remove_fn
                 remove_fn.extend(quote! {
-                    if index < self.#var_name.len() {
+                    if index < #len_code {
+                        #row_code
                         self.#var_remove_name.write().unwrap().insert(index);
                         return;
                     };
-                    index -= self.#var_name.len();
+                    index -= #len_code;
                 });
                 index += 1;
             }
             //new_fn
+            let len_name = if tracked { "rows" } else { "ve" };
             for i in 0..index {
                 let val: TokenStream = parse_str(format!("ve{},rve{},", i, i).as_str()).unwrap();
                 new_fn.extend(val);
                 if index == (i + 1) {
-                    let val: TokenStream = parse_str(format!("ve{}.len(),", i).as_str()).unwrap();
+                    let val: TokenStream =
+                        parse_str(format!("{}{}.len(),", len_name, i).as_str()).unwrap();
                     new_fn_len.extend(val);
                 } else {
-                    let val: TokenStream = parse_str(format!("ve{}.len() +", i).as_str()).unwrap();
+                    let val: TokenStream =
+                        parse_str(format!("{}{}.len() +", len_name, i).as_str()).unwrap();
                     new_fn_len.extend(val);
                 }
             }
+            let (derive, last_run) = if tracked {
+                (quote! {#[derive(Clone)]}, quote! {last_run: u64,})
+            } else {
+                (quote! {#[derive(Copy, Clone)]}, TokenStream::new())
+            };
             //arch_type_type
             for arch_type_name in &input_arch_type.arch_type_type {
-                let val: TokenStream = parse_str(
-                    match arch_type_name {
-                        MemberType::Normal(t) => {
-                            format!("{}", t)
-                        }
-                        MemberType::Trait(t) => {
-                            format!("dyn {}", t)
-                        }
-                    }
-                    .as_str(),
-                )
-                .unwrap();
-                arch_type_type.extend(quote! {&'a #val,});
+                let val = get_member_type(arch_type_name);
+                arch_type_type.extend(quote! {#val,});
             }
 
             code.extend(quote! {
-                        #[derive(Copy, Clone)]
+                        #derive
                         pub struct #arch_type_name<#life_time> {
                         #members
+                        #rows_members
                         len: usize,
                     }
                         impl<#life_time> #arch_type_name<#life_time> {
                 pub fn new(
                     #members
+                    #last_run
                 ) -> Self {
+                    #rows_init
                     #arch_type_name {
                         #new_fn
                         len: #new_fn_len
+                        #rows_fn
                     }
                 }
             }
@@ -434,15 +871,141 @@ pub fn generate_arch_types(arch_types: &ArchTypes) -> TokenStream {
         }
     }
 
+    code.extend(generate_arch_storage(arch_types));
+    code.extend(generate_arch_world(arch_types));
+
     quote! {
     use crate::corrosive_engine::auto_prelude::*;
-    use corrosive_ecs_core::ecs_core::EngineArch;
-    use std::collections::HashSet;
+    use corrosive_ecs_core::ecs_core::{DynMut, EngineArch, EngineWorld, Entity, Tracked};
+    use std::any::{Any, TypeId};
+    use std::collections::{HashMap, HashSet};
+    use std::marker::PhantomData;
+    use std::mem::take;
     use std::sync::RwLock;
             #code
             }
 }
 
+/// Generates the storage of every archetype.
+/// Each component type is kept in its own column, so iterating over a component reads contiguous memory.
+/// Rows are only put together when entities are added, moved or removed.
+fn generate_arch_storage(arch_types: &ArchTypes) -> TokenStream {
+    let mut code: TokenStream = TokenStream::new();
+
+    for (index, arch_type) in arch_types.arch_types.iter().enumerate() {
+        let storage_name: TokenStream = parse_str(arch_storage_name(index).as_str()).unwrap();
+        let entity_index: TokenStream = parse_str(format!("{}", arch_type.len()).as_str()).unwrap();
+        let mut columns: TokenStream = TokenStream::new();
+        let mut row_type: TokenStream = TokenStream::new();
+        let mut push_code: TokenStream = TokenStream::new();
+        let mut swap_remove_code: TokenStream = TokenStream::new();
+        let mut iter_code: TokenStream = TokenStream::new();
+        let mut next_code: TokenStream = TokenStream::new();
+
+        for (i, component) in arch_type.iter().enumerate() {
+            let t: TokenStream = parse_str(component).unwrap();
+            let column: TokenStream = parse_str(format!("c{}", i).as_str()).unwrap();
+            let i: TokenStream = parse_str(format!("{}", i).as_str()).unwrap();
+            columns.extend(quote! {pub #column: Vec<#t>,});
+            row_type.extend(quote! {#t,});
+            push_code.extend(quote! {self.#column.push(row.#i);});
+            swap_remove_code.extend(quote! {self.#column.swap_remove(row),});
+            iter_code.extend(quote! {let mut #column = take(&mut self.#column).into_iter();});
+            next_code.extend(quote! {#column.next().unwrap(),});
+        }
+
+        code.extend(quote! {
+            #[derive(Default)]
+            pub struct #storage_name {
+                #columns
+                pub entities: Vec<Entity>,
+            }
+            impl #storage_name {
+                pub fn len(&self) -> usize {
+                    self.entities.len()
+                }
+                pub fn is_empty(&self) -> bool {
+                    self.entities.is_empty()
+                }
+                pub fn push(&mut self, row: (#row_type Entity,)) {
+                    #push_code
+                    self.entities.push(row.#entity_index);
+                }
+                pub fn swap_remove(&mut self, row: usize) -> (#row_type Entity,) {
+                    (#swap_remove_code self.entities.swap_remove(row),)
+                }
+                /// Empties the columns and returns their rows.
+                pub fn take_rows(&mut self) -> Vec<(#row_type Entity,)> {
+                    #iter_code
+                    take(&mut self.entities)
+                        .into_iter()
+                        .map(|entity| (#next_code entity,))
+                        .collect()
+                }
+            }
+        });
+    }
+
+    code
+}
+
+/// Generates the lookup of entities used by the `World` input.
+fn generate_arch_world(arch_types: &ArchTypes) -> TokenStream {
+    let mut members: TokenStream = TokenStream::new();
+    let mut get_code: TokenStream = TokenStream::new();
+    let mut contains_code: TokenStream = TokenStream::new();
+
+    for (index, arch_type) in arch_types.arch_types.iter().enumerate() {
+        let arch_name: TokenStream = parse_str(format!("a{}", index).as_str()).unwrap();
+        let entities_name: TokenStream = parse_str(format!("ae{}", index).as_str()).unwrap();
+        let storage_name: TokenStream = parse_str(arch_storage_name(index).as_str()).unwrap();
+        let mut component_code: TokenStream = TokenStream::new();
+
+        for (i, component) in arch_type.iter().enumerate() {
+            let t: TokenStream = parse_str(component).unwrap();
+            let i: TokenStream = parse_str(format!("c{}", i).as_str()).unwrap();
+            component_code.extend(quote! {
+                if type_id == TypeId::of::<#t>() {
+                    return Some(&self.#arch_name.#i[*row]);
+                }
+            });
+        }
+
+        members.extend(quote! {
+            pub #arch_name: &'a #storage_name,
+            pub #entities_name: &'a HashMap<Entity, usize>,
+        });
+        get_code.extend(quote! {
+            if let Some(row) = self.#entities_name.get(&entity) {
+                #component_code
+                return None;
+            }
+        });
+        contains_code.extend(quote! {
+            if self.#entities_name.contains_key(&entity) {
+                return true;
+            }
+        });
+    }
+
+    quote! {
+        pub struct ArchWorld<'a> {
+            #members
+            pub _marker: PhantomData<&'a ()>,
+        }
+        impl<'a> EngineWorld for ArchWorld<'a> {
+            fn get_component(&self, entity: Entity, type_id: TypeId) -> Option<&dyn Any> {
+                #get_code
+                None
+            }
+            fn contains(&self, entity: Entity) -> bool {
+                #contains_code
+                false
+            }
+        }
+    }
+}
+
 struct MacroReplacer {
     var_arch: Vec<Stmt>,
     var_signals: Vec<Stmt>,
@@ -458,6 +1021,8 @@ struct MacroReplacer {
     index_arch: HashMap<Vec<(String, String)>, usize>,
     index_signals: HashMap<String, usize>,
     is_reset: bool,
+    is_exit: bool,
+    exit_only: bool,
 }
 
 impl MacroReplacer {
@@ -477,26 +1042,35 @@ impl MacroReplacer {
             index_arch: HashMap::new(),
             index_signals: HashMap::new(),
             is_reset: false,
+            is_exit: false,
+            exit_only: false,
         }
     }
 }
 
 impl VisitMut for MacroReplacer {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        visit_mut::visit_expr_mut(self, expr);
+
+        if let Expr::Macro(mac) = expr {
+            if mac.mac.path.is_ident("exit") {
+                self.is_exit = true;
+                *expr = parse2(quote! { engine_exit.trigger() })
+                    .expect("Failed to parse TokenStream into Expr");
+            }
+        }
+    }
+
     fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
         // Process nested statements first
         visit_mut::visit_stmt_mut(self, stmt);
 
         let new_stmt: Stmt = if let Stmt::Macro(mac) = stmt {
-            match mac
-                .mac
-                .path
-                .segments
-                .last()
-                .unwrap()
-                .ident
-                .to_string()
-                .as_str()
-            {
+            let name = mac.mac.path.segments.last().unwrap().ident.to_string();
+            if self.exit_only && name != "exit" {
+                return;
+            }
+            match name.as_str() {
                 "add_entity" => {
                     let mut type_flag = true;
                     let mut ty: Vec<String> = vec!["".to_string()];
@@ -549,7 +1123,9 @@ impl VisitMut for MacroReplacer {
                         }
                     }
 
+                    vec_input.extend(quote! {Entity::allocate(),});
                     if is_new {
+                        vec_type.extend(quote! {Entity,});
                         self.var_arch.push(
                             parse2(quote! {let mut #vec_name: Vec<(#vec_type)> = Vec::new();})
                                 .expect("Failed to parse TokenStream into Stmt"),
@@ -606,6 +1182,11 @@ impl VisitMut for MacroReplacer {
                     parse2(quote! { engine_signal_trigger = true; })
                         .expect("Failed to parse TokenStream into Stmt")
                 }
+                "exit" => {
+                    self.is_exit = true;
+                    parse2(quote! { engine_exit.trigger(); })
+                        .expect("Failed to parse TokenStream into Stmt")
+                }
                 _ => stmt.clone(),
             }
         } else {
@@ -615,6 +1196,18 @@ impl VisitMut for MacroReplacer {
     }
 }
 
+/// Replaces `exit!()` in the body of a task with a trigger of `engine_exit`,
+/// the `Exit` output that `#[task]` adds to the task.
+/// Returns true should the body use `exit!()`.
+pub fn replace_exit_macro(stmts: &mut [Stmt]) -> bool {
+    let mut replacer: MacroReplacer = MacroReplacer::new();
+    replacer.exit_only = true;
+    for stmt in stmts.iter_mut() {
+        replacer.visit_stmt_mut(stmt);
+    }
+    replacer.is_exit
+}
+
 pub fn generate_task_body(stmts: &mut Vec<Stmt>) -> TokenStream {
     let mut replacer: MacroReplacer = MacroReplacer::new();
     for stmt in stmts.iter_mut() {
@@ -658,25 +1251,47 @@ pub fn generate_task_body(stmts: &mut Vec<Stmt>) -> TokenStream {
 pub fn generate_app_body(
     all_tasks: &HashMap<&String, Task>,
     task_options: &HashMap<&String, &(TaskType, Option<LogicalExpression>)>,
-    setup_dependency_map: &DependencyGraph,
-    sync_dependency_map: &DependencyGraph,
-    runtime_dependency_map: &DependencyGraph,
+    schedules: &Schedules,
     arch_types: &ArchTypes,
+    fixed_rate: Option<f64>,
 ) -> TokenStream {
-    let variables = generate_app_variables(arch_types, task_options);
+    let Schedules {
+        setup: setup_dependency_map,
+        sync: sync_dependency_map,
+        runtime: runtime_dependency_map,
+        shutdown: shutdown_dependency_map,
+        teardown: teardown_dependency_map,
+        transition: transition_dependency_map,
+    } = schedules;
+    let EngineFields {
+        fields,
+        init: fields_init,
+        names: fields_names,
+    } = generate_app_variables(arch_types, task_options, fixed_rate);
     let overwrite = generate_app_overwrite(arch_types);
+    let commands = generate_app_commands(arch_types, &fields_names);
+    let mut events_update: TokenStream = TokenStream::new();
+    for event in &arch_types.events {
+        let channel_name: TokenStream = parse_str(event_channel_name(event).as_str()).unwrap();
+        events_update.extend(quote! {#channel_name.update();});
+    }
+    for component in &arch_types.removed {
+        let channel_name: TokenStream =
+            parse_str(removed_channel_name(component).as_str()).unwrap();
+        events_update.extend(quote! {#channel_name.update();});
+    }
     let mut runtime_bus = generate_bus_channels(runtime_dependency_map);
     let setup_bus = generate_bus_channels(setup_dependency_map);
     let mut runtime_tasks: TokenStream = TokenStream::new();
     let mut runtime_joins: TokenStream = TokenStream::new();
     let mut setup_tasks: TokenStream = TokenStream::new();
     let mut setup_joins: TokenStream = TokenStream::new();
-    let mut sync_tasks: TokenStream = TokenStream::new();
     for task in generate_app_task_body(
         &all_tasks,
         &task_options,
         &arch_types,
         &runtime_dependency_map,
+        true,
     ) {
         runtime_tasks.extend(task.1);
         let name: TokenStream = parse_str(format!("{}_end", task.0).as_str()).unwrap();
@@ -689,61 +1304,194 @@ pub fn generate_app_body(
         &task_options,
         &arch_types,
         &setup_dependency_map,
+        true,
     ) {
         let name: TokenStream = parse_str(format!("handle_{}", task.0).as_str()).unwrap();
         let task: TokenStream = task.1;
         setup_tasks.extend(quote! {let #name = #task});
         setup_joins.extend(quote! {#name.join().expect("TODO: panic message");})
     }
-    for task in sync_dependency_map.topological_sort().unwrap() {
-        let tasks =
-            generate_app_task_body(&all_tasks, &task_options, &arch_types, &sync_dependency_map);
-        if let DependencyType::Task(v) = task {
-            sync_tasks.extend(tasks[&v].clone())
-        }
-    }
+    let sync_tasks =
+        generate_sequential_tasks(all_tasks, task_options, arch_types, sync_dependency_map);
+    let step_setup_tasks =
+        generate_sequential_tasks(all_tasks, task_options, arch_types, setup_dependency_map);
+    let step_runtime_tasks =
+        generate_sequential_tasks(all_tasks, task_options, arch_types, runtime_dependency_map);
+    let shutdown_tasks =
+        generate_sequential_tasks(all_tasks, task_options, arch_types, shutdown_dependency_map);
+    let teardown_tasks =
+        generate_sequential_tasks(all_tasks, task_options, arch_types, teardown_dependency_map);
+    let transition_tasks = generate_transition_tasks(
+        all_tasks,
+        task_options,
+        arch_types,
+        transition_dependency_map,
+    );
 
     quote! {
-        pub fn run_engine(){
-        #variables
-        let mut loop_trigger = Trigger::new();
-        #runtime_bus
-        thread::scope(|s: &Scope| {
-            #runtime_tasks
-            if reset.load(SeqCst) {
-                #setup_bus
-                thread::scope(|s: &Scope| {
-                reset.store(false, Ordering::SeqCst);
-                #setup_tasks
-                #setup_joins
-                });
+        use crate::corrosive_engine::auto_prelude::{*};
+        use corrosive_ecs_core::ecs_core::{*};
+        use std::collections::{HashMap, HashSet};
+        use std::marker::PhantomData;
+        use std::mem::take;
+        use std::any::{Any, TypeId};
+        use std::sync::atomic::Ordering::SeqCst;
+        use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, Ordering};
+        use std::sync::RwLock;
+        use std::thread;
+        use std::thread::{Scope, ScopedJoinHandle};
+        use std::time::Instant;
+
+        /// Holds the archetypes, resources, states and hierarchies of the engine.
+        /// `run` runs the engine on its own threads,
+        /// while `setup`, `step` and `step_n` run the tasks one frame at a time.
+        #[allow(non_snake_case)]
+        pub struct Engine {
+            #fields
+        }
+        impl Default for Engine {
+            fn default() -> Self {
+                Self::new()
             }
-            loop{
-                #overwrite
-
-                current_time = Instant::now();
-                let new_current_time = current_time
-                    .duration_since(last_time)
-                    .as_secs_f64()
-                    .to_bits();
-                delta_time.store(new_current_time.clone(), Ordering::Relaxed);
-                last_time = current_time;
-
-                fixed_delta_time += new_current_time;
-                if (fixed_time.load(Ordering::Relaxed) <= fixed_delta_time) {
-                    fixed_delta_time = 0;
-                    is_fixed.store(true, SeqCst);
-                } else {
-                    is_fixed.store(false, SeqCst);
+        }
+        #[allow(non_snake_case, unused_variables, unused_mut)]
+        impl Engine {
+            pub fn new() -> Self {
+                Engine {
+                    #fields_init
+                }
+            }
+            /// Runs the setup tasks in dependency order.
+            /// Runs the teardown tasks first should the engine have been set up before.
+            pub fn setup(&self) {
+                let Engine { #fields_names } = self;
+                if is_setup.swap(true, SeqCst) {
+                    self.teardown();
+                }
+                reset.store(false, SeqCst);
+                #step_setup_tasks
+            }
+            /// Runs the teardown tasks in dependency order.
+            pub fn teardown(&self) {
+                let Engine { #fields_names } = self;
+                #teardown_tasks
+            }
+            /// Runs the shutdown tasks in dependency order.
+            pub fn shutdown(&self) {
+                let Engine { #fields_names } = self;
+                #shutdown_tasks
+            }
+            /// Runs the `on_enter` and `on_exit` tasks of the states that changed since the last frame,
+            /// in dependency order.
+            pub fn run_transitions(&self) {
+                let Engine { #fields_names } = self;
+                #transition_tasks
+            }
+            /// Runs a single frame with the given delta time.
+            /// Runs the setup tasks first should the engine need a reset.
+            /// Does nothing once the engine has exited.
+            pub fn step(&self, delta: f64) {
+                if self.exit.load(SeqCst) {
+                    return;
+                }
+                if self.reset.load(SeqCst) {
+                    self.setup();
+                }
+                self.merge();
+                self.run_transitions();
+                self.update_time(delta);
+                self.run_sync();
+                let Engine { #fields_names } = self;
+                #step_runtime_tasks
+                if exit.load(SeqCst) {
+                    self.teardown();
+                    self.shutdown();
+                }
+            }
+            /// Runs `n` frames with the given delta time.
+            pub fn step_n(&self, n: usize, delta: f64) {
+                for _ in 0..n {
+                    self.step(delta);
                 }
+            }
+            /// Runs the engine on its own threads until a task triggers `Exit`.
+            pub fn run(&self) {
+                let Engine { #fields_names } = self;
+                let mut last_time = Instant::now();
+                let mut current_time = Instant::now();
+                let mut loop_trigger = Trigger::new();
+                let engine_stop = AtomicBool::new(false);
+                #runtime_bus
+                thread::scope(|s: &Scope| {
+                    #runtime_tasks
+                    loop{
+                        if reset.load(SeqCst) {
+                            if is_setup.swap(true, SeqCst) {
+                                self.teardown();
+                            }
+                            #setup_bus
+                            thread::scope(|s: &Scope| {
+                            reset.store(false, Ordering::SeqCst);
+                            #setup_tasks
+                            #setup_joins
+                            });
+                        }
+                        self.merge();
+                        self.run_transitions();
 
-                #sync_tasks
+                        current_time = Instant::now();
+                        self.update_time(current_time.duration_since(last_time).as_secs_f64());
+                        last_time = current_time;
+
+                        self.run_sync();
 
-                loop_trigger.trigger();
+                        loop_trigger.trigger();
 
-                #runtime_joins
+                        #runtime_joins
+
+                        if exit.load(SeqCst) {
+                            break;
+                        }
+                    }
+                    engine_stop.store(true, SeqCst);
+                    loop_trigger.trigger();
+                });
+                self.teardown();
+                self.shutdown();
+            }
+            fn merge(&self) {
+                let Engine { #fields_names } = self;
+                thread::scope(|s: &Scope| {
+                    #overwrite
+                });
+                self.apply_commands();
+                #events_update
+            }
+            #commands
+            fn update_time(&self, delta: f64) {
+                let Engine { #fields_names } = self;
+                let time_scale = *r_TimeScale.f_read();
+                paused.store(time_scale.paused, SeqCst);
+                let delta = time_scale.apply(delta);
+
+                delta_time.store(delta.to_bits(), Ordering::Relaxed);
+                let elapsed = f64::from_bits(elapsed_time.load(Ordering::Relaxed)) + delta;
+                elapsed_time.store(elapsed.to_bits(), Ordering::Relaxed);
+                frame_count.fetch_add(1, Ordering::Relaxed);
+
+                let mut fixed_time = r_FixedTime.f_write();
+                fixed_steps.store(fixed_time.advance(delta), SeqCst);
+                fixed_delta_time.store(fixed_time.timestep.to_bits(), Ordering::Relaxed);
             }
-        });}
+            fn run_sync(&self) {
+                let Engine { #fields_names } = self;
+                #sync_tasks
+            }
+        }
+
+        pub fn run_engine(){
+            Engine::new().run();
+        }
     }
 }
 fn generate_app_task_body<'a>(
@@ -751,6 +1499,7 @@ fn generate_app_task_body<'a>(
     task_options: &'a HashMap<&String, &(TaskType, Option<LogicalExpression>)>,
     arch_types: &ArchTypes,
     dependency_graph: &'a DependencyGraph,
+    threaded: bool,
 ) -> HashMap<&'a String, TokenStream> {
     let mut task_codes: HashMap<&'a String, TokenStream> = HashMap::new();
 
@@ -770,7 +1519,7 @@ fn generate_app_task_body<'a>(
         let mut arch_types_index: usize = 0;
         for task in &tasks[task_name].inputs {
             match task {
-                TaskInput::Arch(_, _) => {
+                TaskInput::Arch(_, _, _) => {
                     let t = &arch_types.tasks[task_name].input[arch_types_index];
                     let arch_name: TokenStream =
                         parse_str(format!("{}{}", task_name, t.task_index).as_str()).unwrap();
@@ -785,6 +1534,9 @@ fn generate_app_task_body<'a>(
                         arch_inputs.extend(quote! {&#remove,});
                     }
 
+                    if has_change_filter(&t.filters) {
+                        arch_inputs.extend(quote! {last_run,});
+                    }
                     code.extend(quote! {
                         Arch::new(&mut #arch_name::new(
                         #arch_inputs
@@ -793,37 +1545,96 @@ fn generate_app_task_body<'a>(
                     arch_types_index += 1;
                 }
                 TaskInput::Resources(_, v) => {
-                    let resource_name: TokenStream = parse_str(
-                        format!("r_{}", v)
-                            .replace("<", "")
-                            .replace(">", "")
-                            .as_str(),
-                    )
-                    .unwrap();
+                    let resource_name: TokenStream =
+                        parse_str(type_field_name("r", v).as_str()).unwrap();
+                    code.extend(quote! {#resource_name.read_only(),})
+                }
+                TaskInput::ResourcesMut(_, v) => {
+                    let resource_name: TokenStream =
+                        parse_str(type_field_name("r", v).as_str()).unwrap();
                     code.extend(quote! {#resource_name.clone(),})
                 }
                 TaskInput::State(_, v) => {
-                    let state_name: TokenStream = parse_str(
-                        format!("st_{}", v)
-                            .replace("<", "")
-                            .replace(">", "")
-                            .as_str(),
-                    )
-                    .unwrap();
+                    let state_name: TokenStream =
+                        parse_str(type_field_name("st", v).as_str()).unwrap();
+                    code.extend(quote! {#state_name.read_only(),})
+                }
+                TaskInput::StateMut(_, v) => {
+                    let state_name: TokenStream =
+                        parse_str(type_field_name("st", v).as_str()).unwrap();
                     code.extend(quote! {#state_name.clone(),})
                 }
                 TaskInput::Hierarchy(_, v) => {
                     let hierarch_name: TokenStream =
-                        parse_str(format!("h_{}", v).as_str()).unwrap();
+                        parse_str(type_field_name("h", v).as_str()).unwrap();
                     code.extend(quote! {#hierarch_name.clone(),})
                 }
                 TaskInput::DeltaTime(_) => {
                     code.extend(quote! {&f64::from_bits(delta_time.load(Ordering::Relaxed)),});
                 }
+                TaskInput::FixedDeltaTime(_) => {
+                    code.extend(
+                        quote! {&f64::from_bits(fixed_delta_time.load(Ordering::Relaxed)),},
+                    );
+                }
+                TaskInput::ElapsedTime(_) => {
+                    code.extend(quote! {&f64::from_bits(elapsed_time.load(Ordering::Relaxed)),});
+                }
+                TaskInput::FrameCount(_) => {
+                    code.extend(quote! {&frame_count.load(Ordering::Relaxed),});
+                }
+                TaskInput::World(_) => {
+                    let mut world_inputs: TokenStream = TokenStream::new();
+                    for i in 0..arch_types.arch_types.len() {
+                        let arch_name: TokenStream = parse_str(format!("a{}", i).as_str()).unwrap();
+                        let entities_name: TokenStream =
+                            parse_str(format!("ae{}", i).as_str()).unwrap();
+                        world_inputs.extend(quote! {
+                            #arch_name: &*#arch_name.read().unwrap(),
+                            #entities_name: &*#entities_name.read().unwrap(),
+                        });
+                    }
+                    code.extend(quote! {
+                        World::new(&ArchWorld {
+                            #world_inputs
+                            _marker: PhantomData,
+                        }, &commands),
+                    });
+                }
+                TaskInput::Commands(_) => {
+                    code.extend(quote! {Commands::new(&commands),});
+                }
+                TaskInput::EventReader(n, v) => {
+                    let channel_name: TokenStream =
+                        parse_str(event_channel_name(v).as_str()).unwrap();
+                    let cursor_name: TokenStream =
+                        parse_str(event_cursor_name(task_name, n).as_str()).unwrap();
+                    code.extend(quote! {EventReader::new(&#channel_name, &#cursor_name),});
+                }
+                TaskInput::EventWriter(_, v) => {
+                    let channel_name: TokenStream =
+                        parse_str(event_channel_name(v).as_str()).unwrap();
+                    code.extend(quote! {EventWriter::new(&#channel_name),});
+                }
+                TaskInput::Removed(n, v) => {
+                    let channel_name: TokenStream =
+                        parse_str(removed_channel_name(v).as_str()).unwrap();
+                    let cursor_name: TokenStream =
+                        parse_str(removed_cursor_name(task_name, n).as_str()).unwrap();
+                    code.extend(quote! {Removed::new(&#channel_name, &#cursor_name),});
+                }
             }
         }
 
+        let last_run_name = last_run_name(task_name);
+        let last_run = if arch_types.last_run_ticks.contains(&last_run_name) {
+            let last_run_name: TokenStream = parse_str(last_run_name.as_str()).unwrap();
+            quote! {let last_run = #last_run_name.swap(next_change_tick(), Ordering::SeqCst);}
+        } else {
+            TokenStream::new()
+        };
         code = quote! {
+            #last_run
             let o = #task_name_code(
                 #code
             );
@@ -861,7 +1672,7 @@ fn generate_app_task_body<'a>(
                     }
 
                     code.extend(quote! {
-                            (&#arch_name).write().unwrap().extend(#name.vec.into_iter().map(|(#map_left)| (#map_right)));
+                            (&#arch_name).write().unwrap().extend(#name.into_iter_with_entities().map(|((#map_left), entity)| (#map_right entity,)));
                         });
                     index += 1;
                     arch_types_index += 1;
@@ -882,21 +1693,51 @@ fn generate_app_task_body<'a>(
                             reset.store(#name.get(), Ordering::Relaxed);
                         }
                     });
+                    index += 1
+                }
+                TaskOutput::Exit => {
+                    let name: TokenStream = parse_str(format!("o.{}", index).as_str()).unwrap();
+
+                    code.extend(quote! {
+                        if #name.get() {
+                            exit.store(true, Ordering::SeqCst);
+                        }
+                    });
+                    index += 1
+                }
+                TaskOutput::Events(v) => {
+                    let name: TokenStream = parse_str(format!("o.{}", index).as_str()).unwrap();
+                    let channel_name: TokenStream =
+                        parse_str(event_channel_name(v).as_str()).unwrap();
+
+                    code.extend(quote! {
+                        #channel_name.send_batch(#name.events);
+                    });
+                    index += 1
                 }
             }
         }
 
         //condition
         if let Some(t) = &task_options[task_name].1 {
-            let c = t.get_code();
+            let c = t.get_code(task_name);
+            let mut once_code = TokenStream::new();
+            for (index, condition) in t.get_stateful().iter().enumerate() {
+                if let LogicalExpression::Once = condition {
+                    let condition_name: TokenStream =
+                        parse_str(run_condition_name(task_name, index).as_str()).unwrap();
+                    once_code.extend(quote! {#condition_name.store(true, Ordering::SeqCst);});
+                }
+            }
             code = quote! {
                 if #c{
+                    #once_code
                     #code
                 }
             };
         }
 
-        if task_options[task_name].0 == TaskType::Long {
+        if task_options[task_name].0 == TaskType::Long && threaded {
             let mut lock_add_code: TokenStream = TokenStream::new();
             let mut lock_sub_code: TokenStream = TokenStream::new();
 
@@ -906,6 +1747,15 @@ fn generate_app_task_body<'a>(
                     lock_names.insert(format!("la{}", input_arch_type_index.0));
                 }
             }
+            if tasks[task_name]
+                .inputs
+                .iter()
+                .any(|x| matches!(x, TaskInput::World(_)))
+            {
+                for i in 0..arch_types.arch_types.len() {
+                    lock_names.insert(format!("la{}", i));
+                }
+            }
             for lock_name in lock_names {
                 let lock_name = parse_str::<TokenStream>(lock_name.as_str()).unwrap();
                 lock_add_code.extend(quote! {#lock_name.fetch_add(1, Ordering::SeqCst);});
@@ -935,14 +1785,25 @@ fn generate_app_task_body<'a>(
 
         if task_options[task_name].0 == TaskType::Fixed {
             code = quote! {
-                if is_fixed.load(SeqCst) {
+                for _ in 0..fixed_steps.load(SeqCst) {
+                    #code
+                }
+            }
+        }
+
+        if matches!(
+            task_options[task_name].0,
+            TaskType::Update | TaskType::Fixed | TaskType::Long
+        ) {
+            code = quote! {
+                if !paused.load(SeqCst) {
                     #code
                 }
             }
         }
 
         //dependency
-        if task_options[task_name].0 != TaskType::Sync {
+        if task_options[task_name].0 != TaskType::Sync && threaded {
             let start_signal = if task_options[task_name].0 == TaskType::Update
                 || task_options[task_name].0 == TaskType::Long
                 || task_options[task_name].0 == TaskType::Fixed
@@ -989,6 +1850,9 @@ fn generate_app_task_body<'a>(
                         #long_task_handle
                         loop {
                             #start_signal
+                            if engine_stop.load(SeqCst) {
+                                break;
+                            }
                             #dependency
                             #code
                             #end_signal
@@ -1003,6 +1867,76 @@ fn generate_app_task_body<'a>(
 
     task_codes
 }
+/// Returns the code of the tasks of a dependency graph, one after another in execution order.
+fn generate_sequential_tasks(
+    all_tasks: &HashMap<&String, Task>,
+    task_options: &HashMap<&String, &(TaskType, Option<LogicalExpression>)>,
+    arch_types: &ArchTypes,
+    dependency_graph: &DependencyGraph,
+) -> TokenStream {
+    let mut code = TokenStream::new();
+    let tasks =
+        generate_app_task_body(all_tasks, task_options, arch_types, dependency_graph, false);
+    for task in dependency_graph.execution_order().unwrap() {
+        if let DependencyType::Task(v) = task {
+            code.extend(tasks[&v].clone())
+        }
+    }
+    code
+}
+/// Generates the tasks of the transition schedule.
+/// Every state a task waits for is checked before any of the tasks run,
+/// so state changes made by these tasks are handled in the next frame.
+/// The `on_exit` tasks run before the `on_enter` tasks.
+fn generate_transition_tasks(
+    all_tasks: &HashMap<&String, Task>,
+    task_options: &HashMap<&String, &(TaskType, Option<LogicalExpression>)>,
+    arch_types: &ArchTypes,
+    dependency_graph: &DependencyGraph,
+) -> TokenStream {
+    let mut checks = TokenStream::new();
+    let mut exit_code = TokenStream::new();
+    let mut enter_code = TokenStream::new();
+    let tasks =
+        generate_app_task_body(all_tasks, task_options, arch_types, dependency_graph, false);
+    for task in dependency_graph.execution_order().unwrap() {
+        if let DependencyType::Task(v) = task {
+            let (entered, state, value) = match &task_options[&v].0 {
+                TaskType::OnEnter(state, value) => (true, state, value),
+                TaskType::OnExit(state, value) => (false, state, value),
+                _ => continue,
+            };
+            let state_name: TokenStream = parse_str(type_field_name("st", state).as_str()).unwrap();
+            let value: TokenStream = parse_str(value).unwrap();
+            let flag_name: TokenStream = parse_str(transition_name(&v).as_str()).unwrap();
+            let run_name: TokenStream = parse_str(format!("run_{}", v).as_str()).unwrap();
+            let task_code = &tasks[&v];
+
+            checks.extend(quote! {
+                let #run_name = {
+                    let now = *#state_name.f_read() == #value;
+                    let was = #flag_name.swap(now, SeqCst);
+                    now != was && now == #entered
+                };
+            });
+            let code = if entered {
+                &mut enter_code
+            } else {
+                &mut exit_code
+            };
+            code.extend(quote! {
+                if #run_name {
+                    #task_code
+                }
+            });
+        }
+    }
+    quote! {
+        #checks
+        #exit_code
+        #enter_code
+    }
+}
 fn generate_bus_channels(dependency_graph: &DependencyGraph) -> TokenStream {
     let mut trigger_code: TokenStream = TokenStream::new();
     let mut bus_code: TokenStream = TokenStream::new();
@@ -1031,12 +1965,16 @@ fn generate_bus_channels(dependency_graph: &DependencyGraph) -> TokenStream {
     }
 }
 impl LogicalExpression {
-    pub fn get_code(&self) -> TokenStream {
+    pub fn get_code(&self, task: &str) -> TokenStream {
+        self.get_code_at(task, &mut 0)
+    }
+    /// `index` counts the stateful conditions, in the order of `get_stateful`.
+    fn get_code_at(&self, task: &str, index: &mut usize) -> TokenStream {
         match self {
             LogicalExpression::Grouped(v) => {
                 let mut code: TokenStream = TokenStream::new();
                 for value in v {
-                    code.extend(value.get_code());
+                    code.extend(value.get_code_at(task, index));
                 }
                 quote! {(#code)}
             }
@@ -1045,18 +1983,37 @@ impl LogicalExpression {
                 quote! {signals.read().unwrap().contains(#v)}
             }
             LogicalExpression::State(n, t) => {
-                let n: TokenStream = parse_str(
-                    format!("st_{}", n)
-                        .replace("<", "")
-                        .replace(">", "")
-                        .as_str(),
-                )
-                .unwrap();
+                let n: TokenStream = parse_str(type_field_name("st", n).as_str()).unwrap();
                 let t: TokenStream = parse_str(t.as_str()).unwrap();
                 quote! {*#n.f_read() == #t}
             }
+            LogicalExpression::Condition(path, input) => {
+                let path: TokenStream = parse_str(path.as_str()).unwrap();
+                let input_name = match input {
+                    ConditionInput::Resource(v) => type_field_name("r", v),
+                    ConditionInput::State(v) => type_field_name("st", v),
+                };
+                let input_name: TokenStream = parse_str(input_name.as_str()).unwrap();
+                quote! {#path(&#input_name.read_only())}
+            }
+            LogicalExpression::Every(n) => {
+                let condition_name: TokenStream =
+                    parse_str(run_condition_name(task, *index).as_str()).unwrap();
+                *index += 1;
+                quote! {#condition_name.fetch_add(1, Ordering::SeqCst) % #n == 0}
+            }
+            LogicalExpression::AfterSeconds(v) => {
+                let v: TokenStream = parse_str(v.as_str()).unwrap();
+                quote! {f64::from_bits(elapsed_time.load(Ordering::Relaxed)) >= #v}
+            }
+            LogicalExpression::Once => {
+                let condition_name: TokenStream =
+                    parse_str(run_condition_name(task, *index).as_str()).unwrap();
+                *index += 1;
+                quote! {!#condition_name.load(Ordering::SeqCst)}
+            }
             LogicalExpression::Not(v) => {
-                let v = v.get_code();
+                let v = v.get_code_at(task, index);
                 quote! {!#v}
             }
             LogicalExpression::Operator(v) => match v {
@@ -1067,48 +2024,196 @@ impl LogicalExpression {
     }
     pub fn get_states(&self) -> HashSet<&String> {
         let mut values: HashSet<&String> = HashSet::new();
-        if let LogicalExpression::State(t, _) = self {
-            values.insert(t);
+        match self {
+            LogicalExpression::State(t, _)
+            | LogicalExpression::Condition(_, ConditionInput::State(t)) => {
+                values.insert(t);
+            }
+            LogicalExpression::Grouped(v) => {
+                for v in v {
+                    values.extend(v.get_states());
+                }
+            }
+            LogicalExpression::Not(v) => values.extend(v.get_states()),
+            _ => {}
         }
-        if let LogicalExpression::Grouped(v) = self {
-            for v in v {
-                values.extend(v.get_states());
+        values
+    }
+    pub fn get_resources(&self) -> HashSet<&String> {
+        let mut values: HashSet<&String> = HashSet::new();
+        match self {
+            LogicalExpression::Condition(_, ConditionInput::Resource(t)) => {
+                values.insert(t);
+            }
+            LogicalExpression::Grouped(v) => {
+                for v in v {
+                    values.extend(v.get_resources());
+                }
             }
+            LogicalExpression::Not(v) => values.extend(v.get_resources()),
+            _ => {}
         }
         values
     }
+    /// Returns the conditions that need an engine field to keep their state, `every` and `once`.
+    pub fn get_stateful(&self) -> Vec<&LogicalExpression> {
+        match self {
+            LogicalExpression::Every(_) | LogicalExpression::Once => vec![self],
+            LogicalExpression::Grouped(v) => v.iter().flat_map(|v| v.get_stateful()).collect(),
+            LogicalExpression::Not(v) => v.get_stateful(),
+            _ => vec![],
+        }
+    }
+}
+/// Fields of the generated engine struct.
+#[derive(Default)]
+struct EngineFields {
+    fields: TokenStream,
+    init: TokenStream,
+    names: TokenStream,
+}
+impl EngineFields {
+    fn add(&mut self, name: &str, field_type: TokenStream, init: TokenStream) {
+        let name: TokenStream = parse_str(name).unwrap();
+        self.fields.extend(quote! {pub #name: #field_type,});
+        self.init.extend(quote! {#name: #init,});
+        self.names.extend(quote! {#name,});
+    }
 }
 fn generate_app_variables(
     arch_types: &ArchTypes,
     task_options: &HashMap<&String, &(TaskType, Option<LogicalExpression>)>,
-) -> TokenStream {
-    let mut arch_code = TokenStream::new();
-    let mut index: usize = 0;
+    fixed_rate: Option<f64>,
+) -> EngineFields {
+    let mut fields = EngineFields::default();
 
     let mut states: HashSet<&String> = HashSet::new();
-
-    for arch_type in &arch_types.arch_types {
-        let name: TokenStream = parse_str(format!("a{}", index).as_str()).unwrap();
-        let overwrite_name: TokenStream = parse_str(format!("o{}", index).as_str()).unwrap();
-        let remove_name: TokenStream = parse_str(format!("or{}", index).as_str()).unwrap();
-        let lock_name: TokenStream = parse_str(format!("la{}", index).as_str()).unwrap();
-
+    let mut resources: HashSet<&String> = HashSet::new();
+
+    fields.add(
+        "signals",
+        quote! {RwLock<HashSet<String>>},
+        quote! {RwLock::new(HashSet::new())},
+    );
+    fields.add(
+        "o_signals",
+        quote! {RwLock<HashSet<String>>},
+        quote! {RwLock::new(HashSet::new())},
+    );
+    fields.add(
+        "delta_time",
+        quote! {AtomicU64},
+        quote! {AtomicU64::new(0.0f64.to_bits())},
+    );
+    fields.add(
+        "fixed_delta_time",
+        quote! {AtomicU64},
+        quote! {AtomicU64::new(0.0f64.to_bits())},
+    );
+    fields.add(
+        "elapsed_time",
+        quote! {AtomicU64},
+        quote! {AtomicU64::new(0.0f64.to_bits())},
+    );
+    fields.add(
+        "frame_count",
+        quote! {AtomicU64},
+        quote! {AtomicU64::new(0)},
+    );
+    fields.add(
+        "paused",
+        quote! {AtomicBool},
+        quote! {AtomicBool::new(false)},
+    );
+    fields.add(
+        "r_TimeScale",
+        quote! {ResMut<TimeScale>},
+        quote! {ResMut::new(TimeScale::default())},
+    );
+    fields.add(
+        "fixed_steps",
+        quote! {AtomicU32},
+        quote! {AtomicU32::new(0)},
+    );
+    fields.add(
+        "r_FixedTime",
+        quote! {ResMut<FixedTime>},
+        match fixed_rate {
+            Some(rate) => quote! {ResMut::new(FixedTime::new(#rate))},
+            None => quote! {ResMut::new(FixedTime::default())},
+        },
+    );
+    fields.add("reset", quote! {AtomicBool}, quote! {AtomicBool::new(true)});
+    fields.add("exit", quote! {AtomicBool}, quote! {AtomicBool::new(false)});
+    fields.add(
+        "commands",
+        quote! {CommandQueue},
+        quote! {CommandQueue::default()},
+    );
+    fields.add(
+        "is_setup",
+        quote! {AtomicBool},
+        quote! {AtomicBool::new(false)},
+    );
+
+    for (index, arch_type) in arch_types.arch_types.iter().enumerate() {
         let mut c = TokenStream::new();
         for arch in arch_type {
             c.extend(parse_str::<TokenStream>(format!("{},", arch).as_str()).unwrap());
         }
-        arch_code.extend(quote! {
-            let #name: RwLock<Vec<(#c)>> = RwLock::new(Vec::new());
-            let #overwrite_name: RwLock<Vec<(#c)>> = RwLock::new(Vec::new());
-            let #remove_name: RwLock<HashSet<usize>> = RwLock::new(HashSet::new());
-            let #lock_name: AtomicU8 = AtomicU8::new(0);
-        });
-        index += 1;
+        let storage_name: TokenStream = parse_str(arch_storage_name(index).as_str()).unwrap();
+        fields.add(
+            format!("a{}", index).as_str(),
+            quote! {RwLock<#storage_name>},
+            quote! {RwLock::new(#storage_name::default())},
+        );
+        fields.add(
+            format!("o{}", index).as_str(),
+            quote! {RwLock<Vec<(#c Entity,)>>},
+            quote! {RwLock::new(Vec::new())},
+        );
+        fields.add(
+            format!("ae{}", index).as_str(),
+            quote! {RwLock<HashMap<Entity, usize>>},
+            quote! {RwLock::new(HashMap::new())},
+        );
+        fields.add(
+            format!("or{}", index).as_str(),
+            quote! {RwLock<HashSet<usize>>},
+            quote! {RwLock::new(HashSet::new())},
+        );
+        fields.add(
+            format!("la{}", index).as_str(),
+            quote! {AtomicU8},
+            quote! {AtomicU8::new(0)},
+        );
     }
 
     for task_option in task_options {
         if let Some(t) = &task_option.1 .1 {
             states.extend(t.get_states());
+            resources.extend(t.get_resources());
+            for (index, condition) in t.get_stateful().iter().enumerate() {
+                let (field_type, init) = match condition {
+                    LogicalExpression::Once => {
+                        (quote! {AtomicBool}, quote! {AtomicBool::new(false)})
+                    }
+                    _ => (quote! {AtomicU64}, quote! {AtomicU64::new(0)}),
+                };
+                fields.add(
+                    run_condition_name(task_option.0, index).as_str(),
+                    field_type,
+                    init,
+                );
+            }
+        }
+        if let TaskType::OnEnter(state, _) | TaskType::OnExit(state, _) = &task_option.1 .0 {
+            states.insert(state);
+            fields.add(
+                transition_name(task_option.0).as_str(),
+                quote! {AtomicBool},
+                quote! {AtomicBool::new(false)},
+            );
         }
     }
 
@@ -1116,72 +2221,80 @@ fn generate_app_variables(
         states.insert(state);
     }
     for state in states {
-        let name: TokenStream = parse_str(
-            format!("st_{}", state)
-                .replace("<", "")
-                .replace(">", "")
-                .as_str(),
-        )
-        .unwrap();
         let t: TokenStream = parse_str(state.as_str()).unwrap();
 
-        arch_code.extend(quote! {
-            let #name: State<#t> = State::new(Default::default());
-        });
+        fields.add(
+            type_field_name("st", state).as_str(),
+            quote! {StateMut<#t>},
+            quote! {StateMut::new(Default::default())},
+        );
     }
 
     for resource in &arch_types.resources {
-        let name: TokenStream = parse_str(
-            format!("r_{}", resource)
-                .replace("<", "")
-                .replace(">", "")
-                .as_str(),
-        )
-        .unwrap();
+        resources.insert(resource);
+    }
+    for resource in resources {
+        if ENGINE_RESOURCES.contains(&resource.as_str()) {
+            continue;
+        }
         let t: TokenStream = parse_str(resource.as_str()).unwrap();
 
-        arch_code.extend(quote! {
-            let #name: Res<#t> = Res::new(Default::default());
-        });
+        fields.add(
+            type_field_name("r", resource).as_str(),
+            quote! {ResMut<#t>},
+            quote! {ResMut::new(Default::default())},
+        );
     }
 
-    for hierarchy in &arch_types.hierarchy {
-        let name: TokenStream = parse_str(format!("h_{}", hierarchy).as_str()).unwrap();
-        let t: TokenStream = parse_str(hierarchy.as_str()).unwrap();
+    for event in &arch_types.events {
+        let t: TokenStream = parse_str(event.as_str()).unwrap();
 
-        arch_code.extend(quote! {
-            let #name: Hierarchy<#t> = Hierarchy::default();
-        });
+        fields.add(
+            event_channel_name(event).as_str(),
+            quote! {EventChannel<#t>},
+            quote! {EventChannel::default()},
+        );
+    }
+    for cursor in &arch_types.event_cursors {
+        fields.add(
+            cursor.as_str(),
+            quote! {AtomicU64},
+            quote! {AtomicU64::new(0)},
+        );
+    }
+    for last_run in &arch_types.last_run_ticks {
+        fields.add(
+            last_run.as_str(),
+            quote! {AtomicU64},
+            quote! {AtomicU64::new(0)},
+        );
+    }
+    for component in &arch_types.removed {
+        fields.add(
+            removed_channel_name(component).as_str(),
+            quote! {EventChannel<Entity>},
+            quote! {EventChannel::default()},
+        );
+    }
+    for cursor in &arch_types.removed_cursors {
+        fields.add(
+            cursor.as_str(),
+            quote! {AtomicU64},
+            quote! {AtomicU64::new(0)},
+        );
     }
-    quote! {
-        use crate::corrosive_engine::auto_prelude::{*};
-        use corrosive_ecs_core::ecs_core::{*};
-        use std::cmp::PartialEq;
-        use std::collections::HashSet;
-        use std::mem::take;
-        use std::sync::atomic::Ordering::SeqCst;
-        use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
-        use std::sync::RwLock;
-        use std::thread;
-        use std::thread::{Scope, ScopedJoinHandle};
-        use std::time::Instant;
-        use corrosive_ecs_core_macro::corrosive_engine_builder;
-        use std::sync::mpsc;
-
-        let mut signals = RwLock::new(HashSet::<String>::new());
-        let mut o_signals = RwLock::new(HashSet::<String>::new());
-        let mut last_time = Instant::now();
-        let mut current_time = Instant::now();
-        let delta_time = AtomicU64::new(0.0f64.to_bits());
-
-        let fixed_time = AtomicU64::new(0.1f64.to_bits());
-        let mut fixed_delta_time: u64 = 0.0f64 as u64;
-        let is_fixed = AtomicBool::new(false);
 
-        let reset: AtomicBool = AtomicBool::new(true);
+    for hierarchy in &arch_types.hierarchy {
+        let t: TokenStream = parse_str(hierarchy.as_str()).unwrap();
 
-        #arch_code
+        fields.add(
+            type_field_name("h", hierarchy).as_str(),
+            quote! {Hierarchy<#t>},
+            quote! {Hierarchy::default()},
+        );
     }
+
+    fields
 }
 fn generate_app_overwrite(arch_types: &ArchTypes) -> TokenStream {
     let mut overwrite_thread_code: TokenStream = TokenStream::new();
@@ -1193,9 +2306,23 @@ fn generate_app_overwrite(arch_types: &ArchTypes) -> TokenStream {
         let overwrite_name: TokenStream = parse_str(format!("o{}", i).as_str()).unwrap();
         let remove_name: TokenStream = parse_str(format!("or{}", i).as_str()).unwrap();
         let lock_name: TokenStream = parse_str(format!("la{}", i).as_str()).unwrap();
+        let entities_name: TokenStream = parse_str(format!("ae{}", i).as_str()).unwrap();
+        let entity_index: TokenStream =
+            parse_str(format!("{}", arch_types.arch_types[i].len()).as_str()).unwrap();
         let mut expire: TokenStream = TokenStream::new();
+        let mut added: TokenStream = TokenStream::new();
+        let mut removed: TokenStream = TokenStream::new();
 
         for j in 0..arch_types.arch_types[i].len() {
+            removed.extend(generate_removed_record(
+                arch_types,
+                &arch_types.arch_types[i][j],
+                &quote! {item.#entity_index},
+            ));
+            if is_tracked(&arch_types.arch_types[i][j]) {
+                let index: TokenStream = parse_str(format!("{}", j).as_str()).unwrap();
+                added.extend(quote! {item.#index.ticks().set_added();})
+            }
             if arch_types.arch_types[i][j].starts_with("Ref<")
                 || arch_types.arch_types[i][j].starts_with("LockedRef<")
                 || arch_types.arch_types[i][j].starts_with("Member<")
@@ -1211,23 +2338,33 @@ fn generate_app_overwrite(arch_types: &ArchTypes) -> TokenStream {
                     return;
                     }
                 let mut write = #arch_name.write().unwrap();
+                let mut entities = #entities_name.write().unwrap();
                 let vlen = write.len();
 
                 if vlen > 0 {
                     let indices_to_remove = take(&mut *#remove_name.write().unwrap());
-                    let mut new = Vec::with_capacity(vlen);
+                    if !indices_to_remove.is_empty() {
+                        for (i, mut item) in write.take_rows().into_iter().enumerate() {
+                            if !indices_to_remove.contains(&i) {
+                                write.push(item);
+                                continue;
+                            }
+                            #expire
+                            #removed
+                            entities.remove(&item.#entity_index);
+                            item.#entity_index.free();
+                        }
 
-                    for (i, mut item) in write.drain(..).enumerate() {
-                        if !indices_to_remove.contains(&i) {
-                            new.push(item);
-                            continue;
+                        for (row, entity) in write.entities.iter().enumerate() {
+                            entities.insert(*entity, row);
                         }
-                        #expire
                     }
-
-                    *write = new;
                 }
-                write.extend(#overwrite_name.write().unwrap().drain(..));
+                for item in #overwrite_name.write().unwrap().drain(..) {
+                    #added
+                    entities.insert(item.#entity_index, write.len());
+                    write.push(item);
+                }
             });
         });
 
@@ -1249,3 +2386,351 @@ fn generate_app_overwrite(arch_types: &ArchTypes) -> TokenStream {
         #overwrite_join_code
     }
 }
+
+/// Generates the methods of the engine that apply queued commands in the merge phase.
+/// Moving an entity splits it into its components and rebuilds it in the archetype
+/// that has the new set of components.
+fn generate_app_commands(arch_types: &ArchTypes, fields_names: &TokenStream) -> TokenStream {
+    let mut locate_code: TokenStream = TokenStream::new();
+    let mut locks: TokenStream = TokenStream::new();
+    let mut type_ids_code: TokenStream = TokenStream::new();
+    let mut take_code: TokenStream = TokenStream::new();
+    let mut put_code: TokenStream = TokenStream::new();
+    let mut resource_code: TokenStream = TokenStream::new();
+    let mut record_code: TokenStream = TokenStream::new();
+    let arch_count = arch_types.arch_types.len();
+
+    for (index, arch_type) in arch_types.arch_types.iter().enumerate() {
+        let arch_name: TokenStream = parse_str(format!("a{}", index).as_str()).unwrap();
+        let entities_name: TokenStream = parse_str(format!("ae{}", index).as_str()).unwrap();
+        let lock_name: TokenStream = parse_str(format!("la{}", index).as_str()).unwrap();
+        let entity_index: TokenStream = parse_str(format!("{}", arch_type.len()).as_str()).unwrap();
+        let mut type_ids: TokenStream = TokenStream::new();
+        let mut split: TokenStream = TokenStream::new();
+        let mut build: TokenStream = TokenStream::new();
+        let mut stamp: TokenStream = TokenStream::new();
+        let mut record: TokenStream = TokenStream::new();
+
+        for (i, component) in arch_type.iter().enumerate() {
+            let removed = generate_removed_record(arch_types, component, &quote! {entity});
+            if !removed.is_empty() {
+                let t: TokenStream = parse_str(component).unwrap();
+                record.extend(quote! {
+                    if removed.contains(&TypeId::of::<#t>()) {
+                        #removed
+                    }
+                });
+            }
+            let t: TokenStream = parse_str(component).unwrap();
+            let i: TokenStream = parse_str(format!("{}", i).as_str()).unwrap();
+            let expire = if component.starts_with("Ref<")
+                || component.starts_with("LockedRef<")
+                || component.starts_with("Member<")
+            {
+                quote! {item.#i.expire();}
+            } else {
+                TokenStream::new()
+            };
+            type_ids.extend(quote! {TypeId::of::<#t>(),});
+            split.extend(quote! {
+                if removed.contains(&TypeId::of::<#t>()) {
+                    #expire
+                } else {
+                    parts.push((TypeId::of::<#t>(), Box::new(item.#i)));
+                }
+            });
+            build.extend(quote! {take_component::<#t>(&mut parts),});
+            if is_tracked(component) {
+                stamp.extend(quote! {
+                    if added.contains(&TypeId::of::<#t>()) {
+                        item.#i.ticks().set_added();
+                    }
+                });
+            }
+        }
+
+        locate_code.extend(quote! {
+            if let Some(row) = #entities_name.read().unwrap().get(&entity) {
+                return Some((#index, *row));
+            }
+        });
+        locks.extend(quote! {#lock_name,});
+        type_ids_code.extend(quote! {#index => vec![#type_ids],});
+        record_code.extend(quote! {#index => {#record}});
+        take_code.extend(quote! {
+            #index => {
+                let mut write = #arch_name.write().unwrap();
+                let mut entities = #entities_name.write().unwrap();
+                let mut item = write.swap_remove(row);
+                entities.remove(&item.#entity_index);
+                if row < write.len() {
+                    entities.insert(write.entities[row], row);
+                }
+                let mut parts: ComponentParts = Vec::new();
+                #split
+                parts
+            }
+        });
+        put_code.extend(quote! {
+            #index => {
+                let item = (#build entity,);
+                #stamp
+                let mut write = #arch_name.write().unwrap();
+                #entities_name.write().unwrap().insert(entity, write.len());
+                write.push(item);
+            }
+        });
+    }
+
+    let mut resources: HashSet<String> = arch_types.resources.clone();
+    resources.extend(ENGINE_RESOURCES.iter().map(|resource| resource.to_string()));
+    for resource in resources {
+        let t: TokenStream = parse_str(resource.as_str()).unwrap();
+        let resource_name: TokenStream =
+            parse_str(type_field_name("r", &resource).as_str()).unwrap();
+        resource_code.extend(quote! {
+            if type_id == TypeId::of::<#t>() {
+                if let Ok(resource) = resource.downcast::<#t>() {
+                    *#resource_name.f_write() = *resource;
+                }
+                return;
+            }
+        });
+    }
+
+    quote! {
+        fn apply_commands(&self) {
+            let Engine { #fields_names } = self;
+            let locks: [&AtomicU8; #arch_count] = [#locks];
+            let mut pending: Vec<Command> = Vec::new();
+
+            for command in commands.take() {
+                match command {
+                    Command::Spawn(entity, parts) => {
+                        let type_ids: Vec<TypeId> = parts.iter().map(|(type_id, _)| *type_id).collect();
+                        let target = Self::find_arch(&type_ids);
+                        debug_assert!(target.is_some(), "no archetype has the components of spawned {:?}, the components must match an archetype that some task outputs", entity);
+                        let Some(target) = target else {
+                            entity.free();
+                            continue;
+                        };
+                        if locks[target].load(Ordering::SeqCst) > 0 {
+                            pending.push(Command::Spawn(entity, parts));
+                            continue;
+                        }
+                        self.put_row(target, parts, entity, &type_ids);
+                    }
+                    Command::Despawn(entity) => {
+                        let Some((arch, row)) = self.locate_entity(entity) else {
+                            continue;
+                        };
+                        if locks[arch].load(Ordering::SeqCst) > 0 {
+                            pending.push(command);
+                            continue;
+                        }
+                        let type_ids = Self::arch_type_ids(arch);
+                        self.record_removed(arch, entity, &type_ids);
+                        self.take_row(arch, row, &type_ids);
+                        entity.free();
+                    }
+                    Command::InsertResource(type_id, resource) => {
+                        self.insert_resource(type_id, resource);
+                    }
+                    Command::InsertComponent(entity, type_id, _) | Command::RemoveComponent(entity, type_id) => {
+                        let Some((arch, row)) = self.locate_entity(entity) else {
+                            continue;
+                        };
+                        if locks[arch].load(Ordering::SeqCst) > 0 {
+                            pending.push(command);
+                            continue;
+                        }
+
+                        let mut type_ids = Self::arch_type_ids(arch);
+                        if let Command::InsertComponent(..) = command {
+                            if !type_ids.contains(&type_id) {
+                                type_ids.push(type_id);
+                            }
+                        } else {
+                            if !type_ids.contains(&type_id) {
+                                continue;
+                            }
+                            type_ids.retain(|x| *x != type_id);
+                        }
+                        let target = Self::find_arch(&type_ids);
+                        debug_assert!(target.is_some(), "no archetype has the components of {:?} after the change, the components must match an archetype that some task outputs", entity);
+                        let Some(target) = target else {
+                            continue;
+                        };
+                        if locks[target].load(Ordering::SeqCst) > 0 {
+                            pending.push(command);
+                            continue;
+                        }
+
+                        if let Command::RemoveComponent(..) = command {
+                            self.record_removed(arch, entity, &[type_id]);
+                        }
+                        let mut parts = self.take_row(arch, row, &[type_id]);
+                        let mut added: Vec<TypeId> = Vec::new();
+                        if let Command::InsertComponent(_, type_id, component) = command {
+                            parts.push((type_id, component));
+                            added.push(type_id);
+                        }
+                        self.put_row(target, parts, entity, &added);
+                    }
+                }
+            }
+
+            for command in pending {
+                commands.push(command);
+            }
+        }
+        fn locate_entity(&self, entity: Entity) -> Option<(usize, usize)> {
+            let Engine { #fields_names } = self;
+            #locate_code
+            None
+        }
+        fn arch_type_ids(arch: usize) -> Vec<TypeId> {
+            match arch {
+                #type_ids_code
+                _ => vec![],
+            }
+        }
+        fn find_arch(type_ids: &[TypeId]) -> Option<usize> {
+            (0..#arch_count).find(|i| {
+                let ids = Self::arch_type_ids(*i);
+                ids.len() == type_ids.len() && ids.iter().all(|x| type_ids.contains(x))
+            })
+        }
+        fn take_row(&self, arch: usize, row: usize, removed: &[TypeId]) -> ComponentParts {
+            let Engine { #fields_names } = self;
+            match arch {
+                #take_code
+                _ => Vec::new(),
+            }
+        }
+        fn put_row(&self, arch: usize, mut parts: ComponentParts, entity: Entity, added: &[TypeId]) {
+            let Engine { #fields_names } = self;
+            match arch {
+                #put_code
+                _ => {}
+            }
+        }
+        fn record_removed(&self, arch: usize, entity: Entity, removed: &[TypeId]) {
+            let Engine { #fields_names } = self;
+            match arch {
+                #record_code
+                _ => {}
+            }
+        }
+        fn insert_resource(&self, type_id: TypeId, resource: Box<dyn Any + Send + Sync>) {
+            let Engine { #fields_names } = self;
+            #resource_code
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arch_task(name: &str, members: Vec<MemberType>) -> Task {
+        Task {
+            name: name.to_string(),
+            inputs: vec![TaskInput::Arch("a".to_string(), members, vec![])],
+            outputs: vec![],
+        }
+    }
+
+    #[test]
+    fn optional_trait_member_visits_each_archetype_once() {
+        let spawn = Task {
+            name: "spawn".to_string(),
+            inputs: vec![],
+            outputs: vec![
+                TaskOutput::Arch(vec!["Hp".to_string()]),
+                TaskOutput::Arch(vec!["Shield".to_string(), "Vel".to_string()]),
+                TaskOutput::Arch(vec!["Vel".to_string()]),
+            ],
+        };
+        let read = arch_task(
+            "read",
+            vec![
+                MemberType::Normal("Entity".to_string()),
+                MemberType::Optional(Box::new(MemberType::Trait("Damageable".to_string()))),
+            ],
+        );
+        let trait_to_components = HashMap::from([(
+            "Damageable".to_string(),
+            HashSet::from(["Hp".to_string(), "Shield".to_string()]),
+        )]);
+
+        let archetypes = get_all_archetypes(vec![&spawn, &read], trait_to_components);
+        let indexes = &archetypes.tasks["read"].input[0].input_arch_type_indexes;
+
+        for (arch, members) in archetypes.arch_types.iter().enumerate() {
+            let iterations: Vec<&Vec<Option<usize>>> = indexes
+                .iter()
+                .filter(|(index, _)| *index == arch)
+                .map(|(_, positions)| positions)
+                .collect();
+            assert_eq!(iterations.len(), 1, "archetype {:?}", members);
+            let has_implementor = members.iter().any(|x| x == "Hp" || x == "Shield");
+            assert_eq!(iterations[0][1].is_some(), has_implementor);
+        }
+    }
+
+    #[test]
+    fn required_trait_member_visits_each_implementor() {
+        let spawn = Task {
+            name: "spawn".to_string(),
+            inputs: vec![],
+            outputs: vec![TaskOutput::Arch(vec![
+                "Hp".to_string(),
+                "Shield".to_string(),
+            ])],
+        };
+        let read = arch_task("read", vec![MemberType::Trait("Damageable".to_string())]);
+        let trait_to_components = HashMap::from([(
+            "Damageable".to_string(),
+            HashSet::from(["Hp".to_string(), "Shield".to_string()]),
+        )]);
+
+        let archetypes = get_all_archetypes(vec![&spawn, &read], trait_to_components);
+        let indexes = &archetypes.tasks["read"].input[0].input_arch_type_indexes;
+
+        assert_eq!(
+            indexes,
+            &vec![(0, vec![Some(0)]), (0, vec![Some(1)])],
+            "{:?}",
+            archetypes.arch_types
+        );
+    }
+
+    #[test]
+    fn exit_macro_becomes_a_trigger() {
+        let mut block: syn::Block = syn::parse_quote! {{
+            if done {
+                exit!();
+            }
+            match state {
+                State::Quit => exit!(),
+                _ => {}
+            }
+        }};
+        assert!(replace_exit_macro(&mut block.stmts));
+
+        let body = quote! {#block}.to_string();
+        assert!(!body.contains("exit !"), "{}", body);
+        assert_eq!(body.matches("engine_exit . trigger ()").count(), 2);
+    }
+
+    #[test]
+    fn other_macros_are_kept() {
+        let mut block: syn::Block = syn::parse_quote! {{
+            signal!("a");
+            println!("b");
+        }};
+        assert!(!replace_exit_macro(&mut block.stmts));
+        assert!(quote! {#block}.to_string().contains("signal !"));
+    }
+}
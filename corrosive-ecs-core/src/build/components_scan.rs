@@ -1,11 +1,13 @@
+use crate::build::error::{BuildError, BuildResult};
 use crate::build::general_scan::{ModifiedState, PathMap};
+use crate::build::type_scan::FileScope;
 use proc_macro2::Ident;
-use quote::ToTokens;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::{fs, io};
 use syn::parse::{Parse, ParseStream};
-use syn::{parse2, File, Item, ItemEnum, ItemStruct, ItemTrait, ItemType, Token, Type};
+use syn::punctuated::Punctuated;
+use syn::{parse2, Attribute, File, Item, ItemEnum, ItemStruct, ItemTrait, ItemType, Token, Type};
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub enum ComponentType {
@@ -47,22 +49,26 @@ impl ComponentMap {
         data
     }
     pub fn get_all(&self) -> HashMap<String, String> {
-        let mut data: HashMap<String, String> = HashMap::new();
+        self.get_all_paths().into_iter().collect()
+    }
+    /// Returns the name and the path of every component and trait, including the ones that share a name.
+    pub fn get_all_paths(&self) -> Vec<(String, String)> {
+        let mut data: Vec<(String, String)> = Vec::new();
         let path = self.path.as_path().iter().last().unwrap().to_str().unwrap();
         for i in &self.components {
             match i {
                 ComponentType::Component(i) => {
-                    data.insert(i.clone(), format!("{}::{}", path, i).to_string());
+                    data.push((i.clone(), format!("{}::{}", path, i).to_string()));
                 }
                 ComponentType::Trait(i) => {
-                    data.insert(i.clone(), format!("{}::{}", path, i).to_string());
+                    data.push((i.clone(), format!("{}::{}", path, i).to_string()));
                 }
                 ComponentType::TraitFor(_, _) => {}
             }
         }
         for i in &self.sub_maps {
-            for i in i.get_all() {
-                data.insert(i.0, format!("{}::{}", path, i.1).to_string());
+            for i in i.get_all_paths() {
+                data.push((i.0, format!("{}::{}", path, i.1).to_string()));
             }
         }
         data
@@ -96,7 +102,16 @@ pub fn write_component_map(component_map: &ComponentMap, file_path: &str) -> io:
     Ok(())
 }
 
-pub fn scan_components(path_map: &PathMap, component_map: &mut ComponentMap) -> io::Result<()> {
+pub fn scan_components(path_map: &PathMap, component_map: &mut ComponentMap) -> BuildResult<()> {
+    let module = FileScope::module_of(&["crate".to_string()], &path_map.path);
+    scan_components_in(path_map, component_map, &module)
+}
+
+fn scan_components_in(
+    path_map: &PathMap,
+    component_map: &mut ComponentMap,
+    module: &[String],
+) -> BuildResult<()> {
     let mut file_to_scan: Option<&PathMap> = None;
     let mut directories_to_scan = Vec::new();
 
@@ -112,10 +127,11 @@ pub fn scan_components(path_map: &PathMap, component_map: &mut ComponentMap) ->
 
     'outer: for directory in directories_to_scan {
         visited_paths.push(directory.path.clone());
+        let sub_module = FileScope::module_of(module, &directory.path);
         for sub_map in &mut component_map.sub_maps {
             if sub_map.path == directory.path {
                 if directory.modified_state == ModifiedState::Changed {
-                    scan_components(directory, sub_map)?;
+                    scan_components_in(directory, sub_map, &sub_module)?;
                 }
                 continue 'outer;
             }
@@ -125,7 +141,7 @@ pub fn scan_components(path_map: &PathMap, component_map: &mut ComponentMap) ->
             sub_maps: vec![],
             components: vec![],
         };
-        scan_components(directory, &mut new_component_map)?;
+        scan_components_in(directory, &mut new_component_map, &sub_module)?;
         component_map.sub_maps.push(new_component_map);
     }
     component_map
@@ -133,100 +149,92 @@ pub fn scan_components(path_map: &PathMap, component_map: &mut ComponentMap) ->
         .retain(|item| visited_paths.contains(&item.path));
 
     if let Some(t) = file_to_scan {
-        component_map.components = find_structs_with_component(t.path.as_path())
+        component_map.components = find_structs_with_component(t.path.as_path(), module)?
     } else {
         component_map.components = Vec::new()
     }
     Ok(())
 }
 
-fn find_structs_with_component(file_path: &Path) -> Vec<ComponentType> {
-    let content = match fs::read_to_string(file_path) {
-        Ok(contents) => contents,
-        Err(err) => {
-            eprintln!("Failed to read file {}: {}", file_path.display(), err);
-            return vec![];
-        }
-    };
+/// Derives of `corrosive_ecs_core_macro` that make an item a component.
+const COMPONENT_DERIVES: [&str; 4] = ["Component", "State", "Resource", "Event"];
 
-    let syntax: File = match syn::parse_file(&content) {
-        Ok(file) => file,
-        Err(err) => {
-            eprintln!("Failed to parse file {}: {}", file_path.display(), err);
-            return vec![];
-        }
-    };
+fn find_structs_with_component(
+    file_path: &Path,
+    module: &[String],
+) -> BuildResult<Vec<ComponentType>> {
+    let content = fs::read_to_string(file_path).map_err(|err| BuildError::io(err, file_path))?;
+
+    let syntax: File = syn::parse_file(&content)
+        .map_err(|err| BuildError::from_syn("failed to parse file", err, file_path))?;
+    let scope = FileScope::new(&syntax, module.to_vec());
 
     let mut names: Vec<ComponentType> = Vec::new();
 
     for item in syntax.items {
         match item {
-            Item::Struct(ItemStruct { attrs, ident, .. }) => {
-                for attr in attrs {
-                    if attr.path().is_ident("derive") {
-                        let tokens = attr.to_token_stream().to_string();
-                        if tokens.contains("Component")
-                            || tokens.contains("State")
-                            || tokens.contains("Resource")
-                        {
-                            names.push(ComponentType::Component(ident.to_string()));
-                        }
-                    }
-                }
+            Item::Struct(ItemStruct { attrs, ident, .. })
+            | Item::Enum(ItemEnum { attrs, ident, .. })
+            | Item::Type(ItemType { attrs, ident, .. })
+                if is_component(&attrs, &scope, file_path)? =>
+            {
+                names.push(ComponentType::Component(ident.to_string()));
             }
-            Item::Enum(ItemEnum { attrs, ident, .. }) => {
-                for attr in attrs {
-                    if attr.path().is_ident("derive") {
-                        let tokens = attr.to_token_stream().to_string();
-                        if tokens.contains("Component")
-                            || tokens.contains("State")
-                            || tokens.contains("Resource")
-                        {
-                            names.push(ComponentType::Component(ident.to_string()));
-                        }
-                    }
-                }
+            Item::Trait(ItemTrait { attrs, ident, .. })
+                if attrs
+                    .iter()
+                    .any(|attr| scope.resolve_name(attr.path()) == "trait_bound") =>
+            {
+                names.push(ComponentType::Trait(ident.to_string()));
             }
-            Item::Type(ItemType { attrs, ident, .. }) => {
-                for attr in attrs {
-                    if attr.path().is_ident("derive") {
-                        let tokens = attr.to_token_stream().to_string();
-                        if tokens.contains("Component")
-                            || tokens.contains("State")
-                            || tokens.contains("Resource")
-                        {
-                            names.push(ComponentType::Component(ident.to_string()));
-                        }
-                    }
-                }
-            }
-            Item::Trait(ItemTrait { attrs, ident, .. }) => {
-                for attr in attrs {
-                    if attr.path().is_ident("trait_bound") {
-                        names.push(ComponentType::Trait(ident.to_string()));
-                        break;
-                    }
-                }
-            }
-            Item::Macro(ref macro_item) => {
-                if macro_item.mac.path.segments.last().unwrap().ident == "trait_for" {
-                    let tokens = macro_item.mac.tokens.clone();
-                    let data: HelperParser =
-                        parse2(tokens).expect("Failed to parse trait_for input");
-                    let trait_name = data.trait_name.to_string();
-                    let types = data
-                        .types
-                        .iter()
-                        .map(|ty| ty.to_token_stream().to_string().replace(" ", ""))
-                        .collect::<HashSet<String>>();
-                    names.push(ComponentType::TraitFor(trait_name, types));
-                }
+            Item::Macro(ref macro_item)
+                if scope.resolve_name(&macro_item.mac.path) == "trait_for" =>
+            {
+                let tokens = macro_item.mac.tokens.clone();
+                let data: HelperParser = parse2(tokens).map_err(|err| {
+                    BuildError::from_syn("failed to parse trait_for! input", err, file_path)
+                })?;
+                let trait_name = scope
+                    .resolve_path(&syn::Path::from(data.trait_name))
+                    .join("::");
+                let types = data
+                    .types
+                    .iter()
+                    .map(|ty| scope.resolve_type(ty))
+                    .collect::<HashSet<String>>();
+                names.push(ComponentType::TraitFor(trait_name, types));
             }
             _ => {}
         }
     }
 
-    names
+    Ok(names)
+}
+
+/// Whether one of the derive attributes derives a component.
+/// The derived paths are resolved through the imports of the file,
+/// so `MyComponentHelper` or a `Component` of another crate do not match.
+fn is_component(attrs: &[Attribute], scope: &FileScope, file_path: &Path) -> BuildResult<bool> {
+    for attr in attrs {
+        if !attr.path().is_ident("derive") {
+            continue;
+        }
+        let derives = attr
+            .parse_args_with(Punctuated::<syn::Path, Token![,]>::parse_terminated)
+            .map_err(|err| BuildError::from_syn("failed to parse derive", err, file_path))?;
+        for derive in derives {
+            let path = scope.resolve_path(&derive);
+            let from_core = path.len() == 1 || path[0] == "corrosive_ecs_core_macro";
+            if from_core
+                && path
+                    .last()
+                    .is_some_and(|name| COMPONENT_DERIVES.contains(&name.as_str()))
+            {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
 }
 struct HelperParser {
     _trait_kw: Token![trait],
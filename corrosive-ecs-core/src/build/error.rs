@@ -0,0 +1,108 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// An error of the build step.
+/// Holds the file it happened in and the line and column when they are known.
+#[derive(Debug)]
+pub struct BuildError {
+    pub message: String,
+    pub path: Option<PathBuf>,
+    /// Line starting from 1 and column starting from 0, as given by `proc_macro2::LineColumn`.
+    pub location: Option<(usize, usize)>,
+}
+
+pub type BuildResult<T> = Result<T, BuildError>;
+
+impl BuildError {
+    pub fn new(message: impl Into<String>) -> Self {
+        BuildError {
+            message: message.into(),
+            path: None,
+            location: None,
+        }
+    }
+
+    /// Error in a file without a known location in it.
+    pub fn in_file(message: impl Into<String>, path: &Path) -> Self {
+        BuildError {
+            message: message.into(),
+            path: Some(path.to_path_buf()),
+            location: None,
+        }
+    }
+
+    /// Error at a span of a file parsed with `syn::parse_file`.
+    pub fn at(message: impl Into<String>, path: &Path, span: Span) -> Self {
+        let start = span.start();
+        BuildError {
+            message: message.into(),
+            path: Some(path.to_path_buf()),
+            location: Some((start.line, start.column)),
+        }
+    }
+
+    /// Error of a failed read or write of a file.
+    pub fn io(error: io::Error, path: &Path) -> Self {
+        BuildError::in_file(error.to_string(), path)
+    }
+
+    /// Error at the span of a `syn::Error` of a file.
+    pub fn from_syn(context: &str, error: syn::Error, path: &Path) -> Self {
+        BuildError::at(format!("{}: {}", context, error), path, error.span())
+    }
+
+    /// Sets the path of an error that has none.
+    pub fn with_path(mut self, path: &Path) -> Self {
+        if self.path.is_none() {
+            self.path = Some(path.to_path_buf());
+        }
+        self
+    }
+
+    /// Prints the error as a cargo warning, so it is shown next to the build output,
+    /// and to stderr, which cargo shows when the build script fails.
+    pub fn report(&self) {
+        println!("cargo:warning=error: {}", self);
+        eprintln!("error: {}", self);
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.path, self.location) {
+            (Some(path), Some((line, column))) => {
+                write!(
+                    f,
+                    "{}:{}:{}: {}",
+                    path.display(),
+                    line,
+                    column + 1,
+                    self.message
+                )
+            }
+            (Some(path), None) => write!(f, "{}: {}", path.display(), self.message),
+            (None, _) => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// Returns the span of the string literal with the value in the tokens.
+pub fn find_literal(tokens: TokenStream, value: &str) -> Option<Span> {
+    let literal = format!("\"{}\"", value);
+    for token in tokens {
+        match token {
+            TokenTree::Literal(t) if t.to_string() == literal => return Some(t.span()),
+            TokenTree::Group(t) => {
+                if let Some(span) = find_literal(t.stream(), value) {
+                    return Some(span);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
@@ -1,36 +1,35 @@
 use crate::build::app_scan::{get_app_package, write_app_package, AppPackage};
 use crate::build::codegen::{create_app, generate_arch_types, generate_prelude, write_rust_file};
 use crate::build::components_scan::{get_component_map, scan_components, write_component_map};
+use crate::build::error::{find_literal, BuildError, BuildResult};
 use crate::build::general_scan::{get_path_map, scan_directory, write_path_map};
-use crate::build::tasks_scan::{get_task_map, scan_tasks, write_task_map};
+use crate::build::tasks_scan::{get_task_map, scan_tasks, write_task_map, TaskMap};
+use crate::build::type_scan::ComponentNames;
+use proc_macro2::TokenStream;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::{env, fs};
+use std::{env, fs, io, process};
 use syn::{parse2, parse_file, Item};
 
+/// Creates the engine of the app at `CORROSIVE_APP_ROOT`.
+/// Should it fail, the error is reported and the build script exits.
 pub fn create_engine() {
-    let mut app_path = env::var("CORROSIVE_APP_ROOT").expect("CORROSIVE_APP_ROOT is not set");
+    if let Err(error) = try_create_engine() {
+        error.report();
+        process::exit(1);
+    }
+}
+/// Creates the engine of the app at `CORROSIVE_APP_ROOT`.
+pub fn try_create_engine() -> BuildResult<()> {
+    let mut app_path = get_app_root()?;
     app_path.push_str("/src/main.rs");
     let main_rs = PathBuf::from(app_path);
-    let content = fs::read_to_string(&main_rs).expect("Failed to read lib");
-
-    let ast = parse_file(&content).expect("Failed to parse main");
-
-    let mut args: Option<AppPackage> = None;
-    for item in ast.items {
-        if let Item::Macro(ref macro_item) = item {
-            if macro_item.mac.path.segments.last().unwrap().ident == "corrosive_engine_builder" {
-                let tokens = macro_item.mac.tokens.clone();
-                args = Some(parse2::<AppPackage>(tokens).expect("Failed to parse macro input"))
-            }
-        }
-    }
-
-    if args.is_none() {
-        panic!("Failed to find corrosive_engine_builder macro in main.rs");
+    let (mut args, tokens) = get_builder_input(&main_rs)?;
+    if env::var("CARGO_FEATURE_HEADLESS").is_ok() {
+        args.headless = true;
     }
-    let args = args.unwrap();
 
-    let mut app_path = env::var("CORROSIVE_APP_ROOT").expect("CORROSIVE_APP_ROOT is not set");
+    let mut app_path = get_app_root()?;
     app_path.push_str("/src");
 
     let path = args.path.clone();
@@ -46,7 +45,7 @@ pub fn create_engine() {
     }
 
     scan_directory(&mut components_path_map, format!("{}/comp", path).as_str())
-        .expect("Failed to scan comp directory");
+        .map_err(io_error(&format!("{}/comp", path)))?;
 
     let mut component_map = get_component_map(
         format!("{}/.corrosive_engine/components.json", app_path).as_str(),
@@ -56,19 +55,13 @@ pub fn create_engine() {
         component_map.path = Path::new(format!("{}/comp", path).as_str()).to_path_buf();
     }
 
-    scan_components(&components_path_map, &mut component_map).expect("Failed to scan components");
+    scan_components(&components_path_map, &mut component_map)?;
 
-    write_component_map(
-        &component_map,
-        format!("{}/.corrosive_engine/components.json", app_path).as_str(),
-    )
-    .expect("Filed to write component map file");
+    let file_path = format!("{}/.corrosive_engine/components.json", app_path);
+    write_component_map(&component_map, &file_path).map_err(io_error(&file_path))?;
 
-    write_path_map(
-        &components_path_map,
-        format!("{}/.corrosive_engine/components_path_map.json", app_path).as_str(),
-    )
-    .expect("Filed to write path map file");
+    let file_path = format!("{}/.corrosive_engine/components_path_map.json", app_path);
+    write_path_map(&components_path_map, &file_path).map_err(io_error(&file_path))?;
 
     let mut tasks_path_map = get_path_map(
         format!("{}/.corrosive_engine/tasks_path_map.json", app_path).as_str(),
@@ -79,7 +72,7 @@ pub fn create_engine() {
     }
 
     scan_directory(&mut tasks_path_map, format!("{}/task", path).as_str())
-        .expect("Failed to scan task directory");
+        .map_err(io_error(&format!("{}/task", path)))?;
 
     let mut task_map = get_task_map(
         format!("{}/.corrosive_engine/tasks.json", app_path).as_str(),
@@ -89,22 +82,14 @@ pub fn create_engine() {
         task_map.path = Path::new(format!("{}/task", path).as_str()).to_path_buf();
     }
 
-    scan_tasks(&tasks_path_map, &mut task_map).expect("Failed to scan tasks");
+    scan_tasks(&tasks_path_map, &mut task_map)?;
 
-    write_task_map(
-        &task_map,
-        format!("{}/.corrosive_engine/tasks.json", app_path).as_str(),
-    )
-    .expect("Filed to write component map file");
+    let file_path = format!("{}/.corrosive_engine/tasks.json", app_path);
+    write_task_map(&task_map, &file_path).map_err(io_error(&file_path))?;
 
-    write_path_map(
-        &tasks_path_map,
-        format!("{}/.corrosive_engine/tasks_path_map.json", app_path).as_str(),
-    )
-    .expect("Filed to write path map file");
+    let file_path = format!("{}/.corrosive_engine/tasks_path_map.json", app_path);
+    write_path_map(&tasks_path_map, &file_path).map_err(io_error(&file_path))?;
 
-    let mut trait_to_components = component_map.get_trait_to_components();
-    let mut tasks = vec![task_map.clone()];
     let mut component_map = vec![(component_map, "crate".to_string())];
     let mut task_map = vec![(task_map, "crate".to_string())];
     let mut app_packages = vec![args];
@@ -141,86 +126,118 @@ pub fn create_engine() {
                                 )
                                 .as_str(),
                             );
-                            for i in &component.get_trait_to_components() {
-                                if let Some(t) = trait_to_components.get_mut(i.0) {
-                                    t.extend(i.1.clone());
-                                } else {
-                                    trait_to_components.insert(i.0.clone(), i.1.clone());
-                                }
-                            }
-                            tasks.push(task.clone());
                             task_map.push((task, folder_name.to_string()));
                             component_map.push((component, folder_name.to_string()));
                             app_packages.push(app_package);
                         }
                     }
                 } else {
-                    panic!("Failed to read an entry.");
+                    return Err(BuildError::new(format!(
+                        "failed to read an entry of {}/.corrosive_engine/packages/",
+                        app_path
+                    )));
                 }
             }
         }
         Err(_) => {
-            panic!(
-                "{}",
-                format!("{}/.corrosive_engine/packages/", app_path).as_str()
+            return Err(BuildError::new(format!(
+                "failed to read {}/.corrosive_engine/packages/",
+                app_path
+            )));
+        }
+    }
+
+    // The scanners write types as paths of their crate, the engine names components as the auto prelude does.
+    let component_names = ComponentNames::new(&component_map);
+    for (app_package, (_, crate_name)) in app_packages.iter_mut().zip(&component_map) {
+        app_package.map_types(&|ty| component_names.engine_type(ty, crate_name, &[]));
+    }
+    let tasks: Vec<TaskMap> = task_map
+        .iter()
+        .map(|(task_map, crate_name)| {
+            task_map.map_types(&|ty, glob_imports| {
+                component_names.engine_type(ty, crate_name, glob_imports)
+            })
+        })
+        .collect();
+    let mut trait_to_components: HashMap<String, HashSet<String>> = HashMap::new();
+    for (component_map, crate_name) in &component_map {
+        for (trait_name, types) in component_map.get_trait_to_components() {
+            trait_to_components
+                .entry(component_names.engine_type(&trait_name, crate_name, &[]))
+                .or_default()
+                .extend(
+                    types
+                        .iter()
+                        .map(|ty| component_names.engine_type(ty, crate_name, &[])),
+                );
+        }
+    }
+
+    let defined_tasks: HashSet<String> = tasks
+        .iter()
+        .flat_map(|task_map| task_map.get_all().into_keys())
+        .collect();
+    for task in app_packages[0].tasks.keys() {
+        if !defined_tasks.contains(task) {
+            let message = format!(
+                "task \"{}\" is not defined, no function marked with #[task] has this name",
+                task
             );
+            return Err(match find_literal(tokens.clone(), task) {
+                Some(span) => BuildError::at(message, &main_rs, span),
+                None => BuildError::in_file(message, &main_rs),
+            });
         }
     }
 
-    let auto_prelude_code = generate_prelude(component_map, task_map);
+    let auto_prelude_code = generate_prelude(component_map, task_map, &component_names);
 
-    write_rust_file(
-        auto_prelude_code,
-        format!("{}/.corrosive_engine/auto_prelude.rs", app_path).as_str(),
-    )
-    .expect("failed to create auto_prelude.ts");
+    let file_path = format!("{}/.corrosive_engine/auto_prelude.rs", app_path);
+    write_rust_file(auto_prelude_code, &file_path).map_err(io_error(&file_path))?;
 
-    let app = create_app(app_packages, tasks, trait_to_components);
+    let app = create_app(app_packages, tasks, trait_to_components)?;
 
-    write_rust_file(
-        generate_arch_types(&app.1),
-        format!("{}/.corrosive_engine/arch_types.rs", app_path).as_str(),
-    )
-    .expect("failed to create arch_types.ts");
+    let file_path = format!("{}/.corrosive_engine/arch_types.rs", app_path);
+    write_rust_file(generate_arch_types(&app.1), &file_path).map_err(io_error(&file_path))?;
 
-    write_rust_file(
-        app.0,
-        format!("{}/.corrosive_engine/engine.rs", app_path).as_str(),
-    )
-    .expect("failed to create arch_types.ts");
+    let file_path = format!("{}/.corrosive_engine/engine.rs", app_path);
+    write_rust_file(app.0, &file_path).map_err(io_error(&file_path))?;
+
+    let file_path = format!("{}/.corrosive_engine/schedule.json", app_path);
+    let schedule = serde_json::to_string_pretty(&app.2)
+        .map_err(|err| BuildError::in_file(err.to_string(), Path::new(&file_path)))?;
+    fs::write(&file_path, schedule).map_err(io_error(&file_path))?;
+    let file_path = format!("{}/.corrosive_engine/schedule.dot", app_path);
+    fs::write(&file_path, app.2.to_dot()).map_err(io_error(&file_path))?;
+
+    for conflict in &app.2.conflicts {
+        println!("cargo:warning={}", conflict);
+    }
+    Ok(())
 }
+/// Writes the app package, components and tasks of a package crate into the app at `CORROSIVE_APP_ROOT`.
+/// Should it fail, the error is reported and the build script exits.
 pub fn create_engine_package(package_name: &str, crate_root: &str) {
-    let mut app_path = env::var("CORROSIVE_APP_ROOT").expect("CORROSIVE_APP_ROOT is not set");
+    if let Err(error) = try_create_engine_package(package_name, crate_root) {
+        error.report();
+        process::exit(1);
+    }
+}
+/// Writes the app package, components and tasks of a package crate into the app at `CORROSIVE_APP_ROOT`.
+pub fn try_create_engine_package(package_name: &str, crate_root: &str) -> BuildResult<()> {
+    let mut app_path = get_app_root()?;
     app_path.push_str(format!("/src/.corrosive_engine/packages/{}", package_name).as_str());
-    fs::create_dir_all(&app_path).unwrap();
+    fs::create_dir_all(&app_path).map_err(io_error(&app_path))?;
 
     //app package
 
     let main_rs = PathBuf::from(format!("{}/src/lib.rs", crate_root).as_str());
-    let content = fs::read_to_string(&main_rs).expect("Failed to read lib");
-
-    let ast = parse_file(&content).expect("Failed to parse lib");
-
-    let mut app_package: Option<AppPackage> = None;
-    for item in ast.items {
-        if let Item::Macro(ref macro_item) = item {
-            if macro_item.mac.path.segments.last().unwrap().ident == "corrosive_engine_builder" {
-                let tokens = macro_item.mac.tokens.clone();
-                app_package =
-                    Some(parse2::<AppPackage>(tokens).expect("Failed to parse macro input"))
-            }
-        }
-    }
-
-    if app_package.is_none() {
-        panic!("Failed to find corrosive_engine_builder macro in lib.rs");
-    }
-
-    let mut app = app_package.unwrap();
+    let (mut app, _) = get_builder_input(&main_rs)?;
     app.name = package_name.to_string();
 
-    write_app_package(&app, format!("{}/app_package.json", app_path).as_str())
-        .expect("Failed to write app package");
+    let file_path = format!("{}/app_package.json", app_path);
+    write_app_package(&app, &file_path).map_err(io_error(&file_path))?;
 
     //component scan
 
@@ -229,14 +246,11 @@ pub fn create_engine_package(package_name: &str, crate_root: &str) {
         &mut components_path_map,
         format!("{}/src/comp", crate_root).as_str(),
     )
-    .expect("Failed to scan comp directory");
+    .map_err(io_error(&format!("{}/src/comp", crate_root)))?;
     let mut component_map = get_component_map("", format!("{}/src/comp", crate_root).as_str());
-    scan_components(&components_path_map, &mut component_map).expect("Failed to scan components");
-    write_component_map(
-        &component_map,
-        format!("{}/components.json", app_path).as_str(),
-    )
-    .expect("Filed to write component map file");
+    scan_components(&components_path_map, &mut component_map)?;
+    let file_path = format!("{}/components.json", app_path);
+    write_component_map(&component_map, &file_path).map_err(io_error(&file_path))?;
 
     //task_scan
 
@@ -246,11 +260,49 @@ pub fn create_engine_package(package_name: &str, crate_root: &str) {
         &mut tasks_path_map,
         format!("{}/src/task", crate_root).as_str(),
     )
-    .expect("Failed to scan task directory");
+    .map_err(io_error(&format!("{}/src/task", crate_root)))?;
 
     let mut task_map = get_task_map("", format!("{}/src/task", crate_root).as_str());
-    scan_tasks(&tasks_path_map, &mut task_map).expect("Failed to scan tasks");
+    scan_tasks(&tasks_path_map, &mut task_map)?;
+
+    let file_path = format!("{}/tasks.json", app_path);
+    write_task_map(&task_map, &file_path).map_err(io_error(&file_path))?;
+    Ok(())
+}
+
+fn get_app_root() -> BuildResult<String> {
+    env::var("CORROSIVE_APP_ROOT")
+        .map_err(|_| BuildError::new("CORROSIVE_APP_ROOT is not set, set it in build.rs"))
+}
+
+/// Returns the parsed `corrosive_engine_builder!` of a file and its tokens.
+fn get_builder_input(path: &Path) -> BuildResult<(AppPackage, TokenStream)> {
+    let content = fs::read_to_string(path).map_err(|err| BuildError::io(err, path))?;
+    let ast = parse_file(&content)
+        .map_err(|err| BuildError::from_syn("failed to parse file", err, path))?;
+
+    for item in ast.items {
+        if let Item::Macro(ref macro_item) = item {
+            if macro_item.mac.path.segments.last().unwrap().ident == "corrosive_engine_builder" {
+                let tokens = macro_item.mac.tokens.clone();
+                let app_package = parse2::<AppPackage>(tokens.clone()).map_err(|err| {
+                    BuildError::from_syn(
+                        "failed to parse corrosive_engine_builder! input",
+                        err,
+                        path,
+                    )
+                })?;
+                return Ok((app_package, tokens));
+            }
+        }
+    }
+
+    Err(BuildError::in_file(
+        "failed to find the corrosive_engine_builder! macro",
+        path,
+    ))
+}
 
-    write_task_map(&task_map, format!("{}/tasks.json", app_path).as_str())
-        .expect("Filed to write component map file");
+fn io_error(path: &str) -> impl FnOnce(io::Error) -> BuildError + '_ {
+    move |err| BuildError::io(err, Path::new(path))
 }
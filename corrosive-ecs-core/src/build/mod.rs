@@ -5,11 +5,17 @@ pub mod app_scan;
 pub mod codegen;
 /// Functions to scan the components.
 pub mod components_scan;
+/// Errors of the build step.
+pub mod error;
 /// General help functions.
 pub mod general_helper;
 /// Functions to scan the overall structure.
 pub mod general_scan;
+/// Functions to describe the resolved schedules.
+pub mod schedule;
 /// Functions to scan the tasks.
 pub mod tasks_scan;
+/// Functions to resolve the types named in the scanned files.
+pub mod type_scan;
 
 pub const ENGINE_DIR: &str = ".corrosive-components";
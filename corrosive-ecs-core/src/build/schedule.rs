@@ -0,0 +1,617 @@
+use crate::build::app_scan::{
+    ConditionInput, DependencyGraph, DependencyType, LogicalExpression, LogicalOperator, TaskType,
+};
+use crate::build::tasks_scan::{ArchFilter, MemberType, Task, TaskInput, TaskOutput};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fmt::Write;
+
+/// The resolved schedules of the engine.
+/// Written to `.corrosive_engine/schedule.json` and `.corrosive_engine/schedule.dot` by the build step.
+#[derive(Serialize, Debug, Default)]
+pub struct ScheduleDump {
+    pub schedules: BTreeMap<String, ScheduleGraph>,
+    pub tasks: BTreeMap<String, TaskInfo>,
+    /// Tasks that run at the same time and lock the same data.
+    pub conflicts: Vec<AccessConflict>,
+}
+/// The nodes of a schedule, its edges and the order the nodes run in when stepping.
+#[derive(Serialize, Debug, Default)]
+pub struct ScheduleGraph {
+    pub nodes: Vec<DependencyType>,
+    /// `(before, after)` pairs.
+    pub edges: Vec<(DependencyType, DependencyType)>,
+    pub order: Vec<DependencyType>,
+}
+/// A task of the engine.
+#[derive(Serialize, Debug)]
+pub struct TaskInfo {
+    pub package: String,
+    pub task_type: String,
+    pub condition: Option<String>,
+    pub reads: BTreeSet<String>,
+    pub writes: BTreeSet<String>,
+}
+
+/// Two tasks of a threaded schedule that are not ordered and lock the same data,
+/// while at least one of them writes to it.
+#[derive(Serialize, Debug, Clone)]
+pub struct AccessConflict {
+    pub schedule: String,
+    pub first: String,
+    pub second: String,
+    pub accesses: BTreeSet<String>,
+}
+impl fmt::Display for AccessConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let accesses: Vec<&str> = self.accesses.iter().map(|x| x.as_str()).collect();
+        write!(
+            f,
+            "tasks \"{}\" and \"{}\" of the {} schedule can run at the same time and both lock {}. \
+            Order them in corrosive_engine_builder! with `after \"{}\"` on \"{}\" or `after \"{}\"` on \"{}\".",
+            self.first,
+            self.second,
+            self.schedule,
+            accesses.join(", "),
+            self.first,
+            self.second,
+            self.second,
+            self.first
+        )
+    }
+}
+
+/// What a task reads and writes.
+/// Members of `Arch` that can be written to, like `Locked<T>`, count as writes.
+/// `ResMut<T>`, `StateMut<T>` and `Hierarchy<T>` count as writes, as they can be written to.
+#[derive(Debug, Default)]
+pub struct TaskAccess {
+    pub reads: BTreeSet<String>,
+    pub writes: BTreeSet<String>,
+    /// What the task takes a lock of while it runs: resources, states, hierarchies
+    /// and the `Locked`, `LockedRef` and `&mut dyn` members of its `Arch` inputs.
+    /// Outputs are merged after the task, so they take no lock.
+    pub locks: BTreeSet<String>,
+}
+
+impl ScheduleDump {
+    pub fn new(
+        schedules: &[(&str, &DependencyGraph)],
+        tasks: &HashMap<&String, Task>,
+        task_options: &HashMap<&String, &(TaskType, Option<LogicalExpression>)>,
+        task_packages: &HashMap<&String, &str>,
+        trait_to_components: &HashMap<String, HashSet<String>>,
+    ) -> Self {
+        let mut dump = ScheduleDump::default();
+
+        for (name, graph) in schedules {
+            let mut nodes: Vec<DependencyType> = graph.in_degrees.keys().cloned().collect();
+            nodes.sort();
+            let mut edges: Vec<(DependencyType, DependencyType)> = graph
+                .dependents
+                .iter()
+                .flat_map(|(after, befores)| {
+                    befores
+                        .iter()
+                        .map(move |before| (before.clone(), after.clone()))
+                })
+                .collect();
+            edges.sort();
+            let order = graph.execution_order().unwrap_or_default();
+
+            dump.schedules.insert(
+                name.to_string(),
+                ScheduleGraph {
+                    nodes,
+                    edges,
+                    order,
+                },
+            );
+        }
+
+        for (name, task) in tasks {
+            let (task_type, condition) = task_options[name];
+            let mut access = get_task_access(task, trait_to_components);
+            if let Some(condition) = condition {
+                access.reads.extend(get_condition_reads(condition));
+            }
+
+            dump.tasks.insert(
+                name.to_string(),
+                TaskInfo {
+                    package: task_packages
+                        .get(name)
+                        .map_or("main".to_string(), |x| x.to_string()),
+                    task_type: get_task_type_string(task_type),
+                    condition: condition.as_ref().map(|condition| match condition {
+                        LogicalExpression::Grouped(v) => get_conditions_string(v),
+                        _ => get_condition_string(condition),
+                    }),
+                    reads: access.reads,
+                    writes: access.writes,
+                },
+            );
+        }
+
+        dump
+    }
+
+    /// Returns the schedules in the Graphviz DOT format, with one cluster per schedule.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph schedule {\n    rankdir=LR;\n    node [shape=box];\n");
+
+        for (name, schedule) in &self.schedules {
+            let _ = writeln!(dot, "    subgraph \"cluster_{}\" {{", name);
+            let _ = writeln!(dot, "        label=\"{}\";", name);
+            for node in &schedule.nodes {
+                let (label, shape) = match node {
+                    DependencyType::Task(t) => match self.tasks.get(t) {
+                        Some(task) => (get_task_label(t, task), "box"),
+                        None => (t.clone(), "box"),
+                    },
+                    _ => (node.to_string(), "ellipse"),
+                };
+                let _ = writeln!(
+                    dot,
+                    "        \"{}/{}\" [label=\"{}\", shape={}];",
+                    name,
+                    node,
+                    escape(&label),
+                    shape
+                );
+            }
+            for (before, after) in &schedule.edges {
+                let _ = writeln!(
+                    dot,
+                    "        \"{}/{}\" -> \"{}/{}\";",
+                    name, before, name, after
+                );
+            }
+            dot.push_str("    }\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Returns the tasks of a threaded schedule that can run at the same time
+/// and lock a resource, state, hierarchy or component that at least one of them writes to.
+pub fn get_access_conflicts(
+    schedule: &str,
+    graph: &DependencyGraph,
+    tasks: &HashMap<&String, Task>,
+    trait_to_components: &HashMap<String, HashSet<String>>,
+) -> Vec<AccessConflict> {
+    let accesses = get_task_accesses(graph, tasks, trait_to_components);
+
+    let mut conflicts = vec![];
+    for (i, (first, first_access)) in accesses.iter().enumerate() {
+        for (second, second_access) in &accesses[i + 1..] {
+            if is_ordered(graph, first, second) {
+                continue;
+            }
+            let conflicting: BTreeSet<String> = get_conflicting(first_access, second_access)
+                .into_iter()
+                .filter(|x| first_access.locks.contains(x) && second_access.locks.contains(x))
+                .collect();
+            if !conflicting.is_empty() {
+                conflicts.push(AccessConflict {
+                    schedule: schedule.to_string(),
+                    first: (*first).clone(),
+                    second: (*second).clone(),
+                    accesses: conflicting,
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+/// Orders the tasks of a threaded schedule that are not ordered yet
+/// and use a resource or state that only one of them writes to, the task that writes runs first.
+/// Tasks that both write are left unordered, `get_access_conflicts` reports them so the order is given in the app.
+pub fn order_writers(
+    graph: &mut DependencyGraph,
+    tasks: &HashMap<&String, Task>,
+    trait_to_components: &HashMap<String, HashSet<String>>,
+) {
+    let accesses = get_task_accesses(graph, tasks, trait_to_components);
+
+    for (i, (first, first_access)) in accesses.iter().enumerate() {
+        for (second, second_access) in &accesses[i + 1..] {
+            if is_ordered(graph, first, second) {
+                continue;
+            }
+            let conflicting: Vec<String> = get_conflicting(first_access, second_access)
+                .into_iter()
+                .filter(|x| x.starts_with("Res<") || x.starts_with("State<"))
+                .collect();
+            if conflicting.is_empty() {
+                continue;
+            }
+            let first_writes = conflicting.iter().any(|x| first_access.writes.contains(x));
+            let second_writes = conflicting.iter().any(|x| second_access.writes.contains(x));
+            if first_writes && second_writes {
+                continue;
+            }
+
+            let (before, after) = if first_writes {
+                (first, second)
+            } else {
+                (second, first)
+            };
+            graph.add_dependency(
+                DependencyType::Task((*before).clone()),
+                DependencyType::Task((*after).clone()),
+            );
+        }
+    }
+}
+
+/// Returns the tasks of a schedule with what they access, sorted by their names.
+fn get_task_accesses<'a>(
+    graph: &DependencyGraph,
+    tasks: &HashMap<&'a String, Task>,
+    trait_to_components: &HashMap<String, HashSet<String>>,
+) -> Vec<(&'a String, TaskAccess)> {
+    let mut accesses: Vec<(&String, TaskAccess)> = tasks
+        .iter()
+        .filter(|(name, _)| {
+            graph
+                .in_degrees
+                .contains_key(&DependencyType::Task((**name).clone()))
+        })
+        .map(|(name, task)| (*name, get_task_access(task, trait_to_components)))
+        .collect();
+    accesses.sort_by(|a, b| a.0.cmp(b.0));
+    accesses
+}
+
+/// Returns what one of the tasks writes to and the other one reads or writes.
+fn get_conflicting(first: &TaskAccess, second: &TaskAccess) -> BTreeSet<String> {
+    first
+        .writes
+        .iter()
+        .filter(|x| second.reads.contains(*x) || second.writes.contains(*x))
+        .chain(second.writes.iter().filter(|x| first.reads.contains(*x)))
+        .cloned()
+        .collect()
+}
+
+/// Whether one of the tasks has to finish before the other one starts.
+fn is_ordered(graph: &DependencyGraph, first: &str, second: &str) -> bool {
+    let first = DependencyType::Task(first.to_string());
+    let second = DependencyType::Task(second.to_string());
+    get_befores(graph, first.clone()).contains(&second)
+        || get_befores(graph, second).contains(&first)
+}
+
+/// Returns every node that has to finish before the node starts.
+fn get_befores(graph: &DependencyGraph, node: DependencyType) -> HashSet<DependencyType> {
+    let mut befores = HashSet::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        if let Some(dependencies) = graph.dependents.get(&node) {
+            for dependency in dependencies {
+                if befores.insert(dependency.clone()) {
+                    stack.push(dependency.clone());
+                }
+            }
+        }
+    }
+    befores
+}
+
+/// Returns what a task reads and writes through its inputs and outputs.
+pub fn get_task_access(
+    task: &Task,
+    trait_to_components: &HashMap<String, HashSet<String>>,
+) -> TaskAccess {
+    let mut access = TaskAccess::default();
+
+    for input in &task.inputs {
+        match input {
+            TaskInput::Arch(_, members, filters) => {
+                for member in members {
+                    add_member_access(&mut access, member, trait_to_components);
+                }
+                for filter in filters {
+                    if let ArchFilter::Changed(t) | ArchFilter::Added(t) = filter {
+                        access.reads.insert(t.clone());
+                    }
+                }
+            }
+            TaskInput::Resources(_, t) => {
+                access.reads.insert(format!("Res<{}>", t));
+                access.locks.insert(format!("Res<{}>", t));
+            }
+            TaskInput::ResourcesMut(_, t) => {
+                access.writes.insert(format!("Res<{}>", t));
+                access.locks.insert(format!("Res<{}>", t));
+            }
+            TaskInput::State(_, t) => {
+                access.reads.insert(format!("State<{}>", t));
+                access.locks.insert(format!("State<{}>", t));
+            }
+            TaskInput::StateMut(_, t) => {
+                access.writes.insert(format!("State<{}>", t));
+                access.locks.insert(format!("State<{}>", t));
+            }
+            TaskInput::Hierarchy(_, t) => {
+                access.writes.insert(format!("Hierarchy<{}>", t));
+                access.locks.insert(format!("Hierarchy<{}>", t));
+            }
+            TaskInput::World(_) => {
+                access.reads.insert("World".to_string());
+            }
+            TaskInput::Commands(_) => {
+                access.writes.insert("Commands".to_string());
+            }
+            TaskInput::EventReader(_, t) => {
+                access.reads.insert(format!("Events<{}>", t));
+            }
+            TaskInput::EventWriter(_, t) => {
+                access.writes.insert(format!("Events<{}>", t));
+            }
+            TaskInput::Removed(_, t) => {
+                access.reads.insert(format!("Removed<{}>", t));
+            }
+            TaskInput::DeltaTime(_)
+            | TaskInput::FixedDeltaTime(_)
+            | TaskInput::ElapsedTime(_)
+            | TaskInput::FrameCount(_) => {}
+        }
+    }
+    for output in &task.outputs {
+        match output {
+            TaskOutput::Arch(components) => {
+                access.writes.extend(components.iter().cloned());
+            }
+            TaskOutput::Events(t) => {
+                access.writes.insert(format!("Events<{}>", t));
+            }
+            TaskOutput::Signal | TaskOutput::Reset | TaskOutput::Exit => {}
+        }
+    }
+
+    access
+}
+
+/// Adds the components a member of an archetype input reads or writes.
+fn add_member_access(
+    access: &mut TaskAccess,
+    member: &MemberType,
+    trait_to_components: &HashMap<String, HashSet<String>>,
+) {
+    match member {
+        MemberType::Normal(t) if t == "Entity" => {}
+        MemberType::Normal(t) => {
+            if ["Locked<", "LockedRef<", "Member<"]
+                .iter()
+                .any(|wrapper| t.starts_with(wrapper))
+            {
+                access.writes.insert(t.clone());
+            } else {
+                access.reads.insert(t.clone());
+            }
+            if ["Locked<", "LockedRef<"]
+                .iter()
+                .any(|wrapper| t.starts_with(wrapper))
+            {
+                access.locks.insert(t.clone());
+            }
+        }
+        MemberType::Trait(t) => {
+            if let Some(components) = trait_to_components.get(t) {
+                access.reads.extend(components.iter().cloned());
+            }
+        }
+        MemberType::TraitMut(t) => {
+            if let Some(components) = trait_to_components.get(t) {
+                let locked = components.iter().map(|x| format!("Locked<{}>", x));
+                access.writes.extend(locked.clone());
+                access.locks.extend(locked);
+            }
+        }
+        MemberType::Optional(t) => add_member_access(access, t, trait_to_components),
+    }
+}
+
+/// Returns the resources and states a condition reads.
+fn get_condition_reads(condition: &LogicalExpression) -> BTreeSet<String> {
+    let mut reads = BTreeSet::new();
+    match condition {
+        LogicalExpression::State(t, _)
+        | LogicalExpression::Condition(_, ConditionInput::State(t)) => {
+            reads.insert(format!("State<{}>", t));
+        }
+        LogicalExpression::Condition(_, ConditionInput::Resource(t)) => {
+            reads.insert(format!("Res<{}>", t));
+        }
+        LogicalExpression::Grouped(v) => {
+            for v in v {
+                reads.extend(get_condition_reads(v));
+            }
+        }
+        LogicalExpression::Not(v) => reads.extend(get_condition_reads(v)),
+        _ => {}
+    }
+    reads
+}
+
+/// Returns a condition as it is written in `corrosive_engine_builder!`.
+pub fn get_condition_string(condition: &LogicalExpression) -> String {
+    match condition {
+        LogicalExpression::Signal(v) => format!("\"{}\"", v),
+        LogicalExpression::State(_, v) => v.replace(" ", ""),
+        LogicalExpression::Condition(path, ConditionInput::Resource(t)) => {
+            format!("{}(Res<{}>)", path, t)
+        }
+        LogicalExpression::Condition(path, ConditionInput::State(t)) => {
+            format!("{}(State<{}>)", path, t)
+        }
+        LogicalExpression::Every(n) => format!("every({})", n),
+        LogicalExpression::AfterSeconds(v) => format!("after_seconds({})", v),
+        LogicalExpression::Once => "once".to_string(),
+        LogicalExpression::Operator(LogicalOperator::And) => "&&".to_string(),
+        LogicalExpression::Operator(LogicalOperator::Or) => "||".to_string(),
+        LogicalExpression::Not(v) => format!("!{}", get_condition_string(v)),
+        LogicalExpression::Grouped(v) => format!("({})", get_conditions_string(v)),
+    }
+}
+
+fn get_conditions_string(conditions: &[LogicalExpression]) -> String {
+    conditions
+        .iter()
+        .map(get_condition_string)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn get_task_type_string(task_type: &TaskType) -> String {
+    match task_type {
+        TaskType::OnEnter(_, v) => format!("OnEnter({})", v.replace(" ", "")),
+        TaskType::OnExit(_, v) => format!("OnExit({})", v.replace(" ", "")),
+        _ => format!("{:?}", task_type),
+    }
+}
+
+fn get_task_label(name: &str, task: &TaskInfo) -> String {
+    let mut label = format!("{}\\n{} [{}]", name, task.task_type, task.package);
+    if let Some(condition) = &task.condition {
+        let _ = write!(label, "\\nif {}", condition);
+    }
+    if !task.reads.is_empty() {
+        let reads: Vec<&str> = task.reads.iter().map(|x| x.as_str()).collect();
+        let _ = write!(label, "\\nreads: {}", reads.join(", "));
+    }
+    if !task.writes.is_empty() {
+        let writes: Vec<&str> = task.writes.iter().map(|x| x.as_str()).collect();
+        let _ = write!(label, "\\nwrites: {}", writes.join(", "));
+    }
+    label
+}
+
+/// Escapes the quotes of a DOT label, line breaks are kept.
+fn escape(label: &str) -> String {
+    label.replace("\"", "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, inputs: Vec<TaskInput>, outputs: Vec<TaskOutput>) -> Task {
+        Task {
+            name: name.to_string(),
+            inputs,
+            outputs,
+        }
+    }
+
+    fn locked_pos_input() -> TaskInput {
+        TaskInput::Arch(
+            "a".to_string(),
+            vec![MemberType::Normal("Locked<Pos>".to_string())],
+            vec![],
+        )
+    }
+
+    fn conflicts_of(tasks: Vec<Task>) -> Vec<AccessConflict> {
+        let names: Vec<String> = tasks.iter().map(|x| x.name.clone()).collect();
+        let mut graph = DependencyGraph::new();
+        for name in &names {
+            graph.add_node(DependencyType::Task(name.clone()));
+        }
+        let tasks: HashMap<&String, Task> = names.iter().zip(tasks).collect();
+        get_access_conflicts("runtime", &graph, &tasks, &HashMap::new())
+    }
+
+    #[test]
+    fn spawned_archetypes_take_no_lock() {
+        let spawn = || vec![TaskOutput::Arch(vec!["Locked<Pos>".to_string()])];
+        let conflicts = conflicts_of(vec![
+            task("spawn_a", vec![], spawn()),
+            task("spawn_b", vec![], spawn()),
+        ]);
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+
+        let conflicts = conflicts_of(vec![
+            task("spawn", vec![], spawn()),
+            task("movement", vec![locked_pos_input()], vec![]),
+        ]);
+        assert!(conflicts.is_empty(), "{:?}", conflicts);
+    }
+
+    #[test]
+    fn locked_inputs_conflict() {
+        let conflicts = conflicts_of(vec![
+            task("knockback", vec![locked_pos_input()], vec![]),
+            task("movement", vec![locked_pos_input()], vec![]),
+        ]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].accesses,
+            BTreeSet::from(["Locked<Pos>".to_string()])
+        );
+        let message = conflicts[0].to_string();
+        assert!(message.contains("`after \"knockback\"` on \"movement\""));
+        assert!(message.contains("`after \"movement\"` on \"knockback\""));
+    }
+
+    fn counter_task(name: &str, writes: bool) -> Task {
+        let input = if writes {
+            TaskInput::ResourcesMut("c".to_string(), "Counter".to_string())
+        } else {
+            TaskInput::Resources("c".to_string(), "Counter".to_string())
+        };
+        task(name, vec![input], vec![])
+    }
+
+    fn ordered_graph(tasks: &[Task]) -> (DependencyGraph, HashMap<&String, Task>) {
+        let mut graph = DependencyGraph::new();
+        for task in tasks {
+            graph.add_node(DependencyType::Task(task.name.clone()));
+        }
+        let tasks: HashMap<&String, Task> = tasks.iter().map(|x| (&x.name, x.clone())).collect();
+        order_writers(&mut graph, &tasks, &HashMap::new());
+        (graph, tasks)
+    }
+
+    #[test]
+    fn writer_runs_before_reader() {
+        let tasks = [
+            counter_task("a_reader", false),
+            counter_task("b_writer", true),
+        ];
+        let (graph, tasks) = ordered_graph(&tasks);
+        assert!(
+            get_befores(&graph, DependencyType::Task("a_reader".to_string()))
+                .contains(&DependencyType::Task("b_writer".to_string()))
+        );
+        assert!(get_access_conflicts("runtime", &graph, &tasks, &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn readers_stay_unordered() {
+        let tasks = [counter_task("a", false), counter_task("b", false)];
+        let (graph, tasks) = ordered_graph(&tasks);
+        assert!(!is_ordered(&graph, "a", "b"));
+        assert!(get_access_conflicts("runtime", &graph, &tasks, &HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn writers_are_reported_instead_of_ordered() {
+        let tasks = [counter_task("a", true), counter_task("b", true)];
+        let (graph, tasks) = ordered_graph(&tasks);
+        assert!(!is_ordered(&graph, "a", "b"));
+        let conflicts = get_access_conflicts("runtime", &graph, &tasks, &HashMap::new());
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].accesses,
+            BTreeSet::from(["Res<Counter>".to_string()])
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use crate::build::codegen::replace_exit_macro;
+use crate::build::error::{BuildError, BuildResult};
 use crate::build::general_scan::{ModifiedState, PathMap};
-use quote::ToTokens;
+use crate::build::type_scan::FileScope;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::{fs, io};
@@ -7,7 +9,7 @@ use syn::punctuated::Punctuated;
 use syn::token::Comma;
 use syn::{
     Attribute, File, FnArg, GenericArgument, Item, ItemFn, Pat, PathArguments, ReturnType, Type,
-    TypeTuple,
+    TypeParamBound, TypeTuple,
 };
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Eq, Hash, PartialEq)]
@@ -20,20 +22,46 @@ pub struct Task {
 pub enum MemberType {
     Normal(String),
     Trait(String),
+    /// `&mut dyn T`, matches the components of the trait stored in `Locked`.
+    TraitMut(String),
+    /// `Option<&T>`, yields `None` for archetypes without the member.
+    Optional(Box<MemberType>),
+}
+/// Filters of an archetype input that do not change the yielded tuple.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Eq, Hash, PartialEq)]
+pub enum ArchFilter {
+    With(String),
+    Without(String),
+    Changed(String),
+    Added(String),
 }
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Eq, Hash, PartialEq)]
 pub enum TaskInput {
-    Arch(String, Vec<MemberType>),
+    Arch(String, Vec<MemberType>, Vec<ArchFilter>),
     Resources(String, String),
+    /// `ResMut<T>`, a resource the task writes to.
+    ResourcesMut(String, String),
     Hierarchy(String, String),
     State(String, String),
+    /// `StateMut<T>`, a state the task writes to.
+    StateMut(String, String),
     DeltaTime(String),
+    FixedDeltaTime(String),
+    ElapsedTime(String),
+    FrameCount(String),
+    World(String),
+    Commands(String),
+    EventReader(String, String),
+    EventWriter(String, String),
+    Removed(String, String),
 }
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Eq, Hash, PartialEq)]
 pub enum TaskOutput {
     Arch(Vec<String>),
     Signal,
     Reset,
+    Exit,
+    Events(String),
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -41,6 +69,9 @@ pub struct TaskMap {
     pub path: PathBuf,
     pub sub_maps: Vec<TaskMap>,
     pub tasks: Vec<Task>,
+    /// Modules the file of the tasks imports with a glob, see `FileScope::glob_imports`.
+    #[serde(default)]
+    pub glob_imports: Vec<String>,
 }
 
 impl TaskMap {
@@ -69,12 +100,90 @@ impl TaskMap {
     }
 }
 
+impl TaskMap {
+    /// Returns the task map with every type in its tasks replaced by `f`,
+    /// which also gets the glob imports of the file of the task.
+    pub fn map_types(&self, f: &impl Fn(&str, &[String]) -> String) -> TaskMap {
+        let map = |ty: &str| f(ty, &self.glob_imports);
+        TaskMap {
+            path: self.path.clone(),
+            sub_maps: self.sub_maps.iter().map(|x| x.map_types(f)).collect(),
+            tasks: self.tasks.iter().map(|x| x.map_types(&map)).collect(),
+            glob_imports: self.glob_imports.clone(),
+        }
+    }
+}
+
+impl Task {
+    fn map_types(&self, f: &impl Fn(&str) -> String) -> Task {
+        Task {
+            name: self.name.clone(),
+            inputs: self.inputs.iter().map(|x| x.map_types(f)).collect(),
+            outputs: self.outputs.iter().map(|x| x.map_types(f)).collect(),
+        }
+    }
+}
+
+impl MemberType {
+    fn map_types(&self, f: &impl Fn(&str) -> String) -> MemberType {
+        match self {
+            MemberType::Normal(t) => MemberType::Normal(f(t)),
+            MemberType::Trait(t) => MemberType::Trait(f(t)),
+            MemberType::TraitMut(t) => MemberType::TraitMut(f(t)),
+            MemberType::Optional(t) => MemberType::Optional(Box::new(t.map_types(f))),
+        }
+    }
+}
+
+impl ArchFilter {
+    fn map_types(&self, f: &impl Fn(&str) -> String) -> ArchFilter {
+        match self {
+            ArchFilter::With(t) => ArchFilter::With(f(t)),
+            ArchFilter::Without(t) => ArchFilter::Without(f(t)),
+            ArchFilter::Changed(t) => ArchFilter::Changed(f(t)),
+            ArchFilter::Added(t) => ArchFilter::Added(f(t)),
+        }
+    }
+}
+
+impl TaskInput {
+    fn map_types(&self, f: &impl Fn(&str) -> String) -> TaskInput {
+        match self {
+            TaskInput::Arch(n, members, filters) => TaskInput::Arch(
+                n.clone(),
+                members.iter().map(|x| x.map_types(f)).collect(),
+                filters.iter().map(|x| x.map_types(f)).collect(),
+            ),
+            TaskInput::Resources(n, t) => TaskInput::Resources(n.clone(), f(t)),
+            TaskInput::ResourcesMut(n, t) => TaskInput::ResourcesMut(n.clone(), f(t)),
+            TaskInput::Hierarchy(n, t) => TaskInput::Hierarchy(n.clone(), f(t)),
+            TaskInput::State(n, t) => TaskInput::State(n.clone(), f(t)),
+            TaskInput::StateMut(n, t) => TaskInput::StateMut(n.clone(), f(t)),
+            TaskInput::EventReader(n, t) => TaskInput::EventReader(n.clone(), f(t)),
+            TaskInput::EventWriter(n, t) => TaskInput::EventWriter(n.clone(), f(t)),
+            TaskInput::Removed(n, t) => TaskInput::Removed(n.clone(), f(t)),
+            input => input.clone(),
+        }
+    }
+}
+
+impl TaskOutput {
+    fn map_types(&self, f: &impl Fn(&str) -> String) -> TaskOutput {
+        match self {
+            TaskOutput::Arch(types) => TaskOutput::Arch(types.iter().map(|t| f(t)).collect()),
+            TaskOutput::Events(t) => TaskOutput::Events(f(t)),
+            output => output.clone(),
+        }
+    }
+}
+
 impl Default for TaskMap {
     fn default() -> Self {
         TaskMap {
             path: Path::new("./").to_path_buf(),
             sub_maps: Vec::new(),
             tasks: Vec::new(),
+            glob_imports: Vec::new(),
         }
     }
 }
@@ -86,6 +195,7 @@ pub fn get_task_map(file_path: &str, default_path: &str) -> TaskMap {
             path: Path::new(default_path).to_path_buf(),
             sub_maps: Vec::new(),
             tasks: Vec::new(),
+            glob_imports: Vec::new(),
         },
     }
 }
@@ -96,7 +206,12 @@ pub fn write_task_map(task_map: &TaskMap, file_path: &str) -> io::Result<()> {
     Ok(())
 }
 
-pub fn scan_tasks(path_map: &PathMap, task_map: &mut TaskMap) -> io::Result<()> {
+pub fn scan_tasks(path_map: &PathMap, task_map: &mut TaskMap) -> BuildResult<()> {
+    let module = FileScope::module_of(&["crate".to_string()], &path_map.path);
+    scan_tasks_in(path_map, task_map, &module)
+}
+
+fn scan_tasks_in(path_map: &PathMap, task_map: &mut TaskMap, module: &[String]) -> BuildResult<()> {
     let mut file_to_scan: Option<&PathMap> = None;
     let mut directories_to_scan = Vec::new();
 
@@ -112,10 +227,11 @@ pub fn scan_tasks(path_map: &PathMap, task_map: &mut TaskMap) -> io::Result<()>
 
     'outer: for directory in directories_to_scan {
         visited_paths.push(directory.path.clone());
+        let sub_module = FileScope::module_of(module, &directory.path);
         for sub_map in &mut task_map.sub_maps {
             if sub_map.path == directory.path {
                 if directory.modified_state == ModifiedState::Changed {
-                    scan_tasks(directory, sub_map)?;
+                    scan_tasks_in(directory, sub_map, &sub_module)?;
                 }
                 continue 'outer;
             }
@@ -124,8 +240,9 @@ pub fn scan_tasks(path_map: &PathMap, task_map: &mut TaskMap) -> io::Result<()>
             path: directory.path.clone(),
             sub_maps: vec![],
             tasks: vec![],
+            glob_imports: vec![],
         };
-        scan_tasks(directory, &mut new_task_map)?;
+        scan_tasks_in(directory, &mut new_task_map, &sub_module)?;
         task_map.sub_maps.push(new_task_map);
     }
     task_map
@@ -133,43 +250,42 @@ pub fn scan_tasks(path_map: &PathMap, task_map: &mut TaskMap) -> io::Result<()>
         .retain(|item| visited_paths.contains(&item.path));
 
     if let Some(t) = file_to_scan {
-        task_map.tasks = find_structs_with_task(t.path.as_path())
+        (task_map.tasks, task_map.glob_imports) = find_structs_with_task(t.path.as_path(), module)?
     } else {
-        task_map.tasks = Vec::new()
+        task_map.tasks = Vec::new();
+        task_map.glob_imports = Vec::new();
     }
     Ok(())
 }
 
-fn find_structs_with_task(file_path: &Path) -> Vec<Task> {
-    let content = match fs::read_to_string(file_path) {
-        Ok(contents) => contents,
-        Err(err) => {
-            eprintln!("Failed to read file {}: {}", file_path.display(), err);
-            return vec![];
-        }
-    };
+/// Returns the tasks of a file along with its glob imports.
+fn find_structs_with_task(
+    file_path: &Path,
+    module: &[String],
+) -> BuildResult<(Vec<Task>, Vec<String>)> {
+    let content = fs::read_to_string(file_path).map_err(|err| BuildError::io(err, file_path))?;
 
-    let syntax: File = match syn::parse_file(&content) {
-        Ok(file) => file,
-        Err(err) => {
-            eprintln!("Failed to parse file {}: {}", file_path.display(), err);
-            return vec![];
-        }
-    };
+    let syntax: File = syn::parse_file(&content)
+        .map_err(|err| BuildError::from_syn("failed to parse file", err, file_path))?;
+    let scope = FileScope::new(&syntax, module.to_vec());
 
     let mut tasks: Vec<Task> = Vec::new();
 
     for item in syntax.items {
         if let Item::Fn(ItemFn {
             attrs,
-            block: _,
+            mut block,
             sig,
             ..
         }) = item
         {
-            if has_task_attr(attrs) {
-                let outputs = get_task_output(sig.output);
-                let inputs = get_task_input(sig.inputs);
+            if has_task_attr(&attrs, &scope) {
+                let mut outputs = get_task_output(sig.output, &scope);
+                // `#[task]` adds an `Exit` output to tasks that use `exit!()`.
+                if replace_exit_macro(&mut block.stmts) {
+                    outputs.push(TaskOutput::Exit);
+                }
+                let inputs = get_task_input(sig.inputs, &scope);
                 tasks.push(Task {
                     name: sig.ident.to_string(),
                     inputs,
@@ -179,20 +295,71 @@ fn find_structs_with_task(file_path: &Path) -> Vec<Task> {
         }
     }
 
-    tasks
+    Ok((tasks, scope.glob_imports()))
 }
 
-fn has_task_attr(attrs: Vec<Attribute>) -> bool {
-    for attr in attrs {
-        let tokens = attr.to_token_stream().to_string();
-        if tokens.contains("task") {
-            return true;
+fn has_task_attr(attrs: &[Attribute], scope: &FileScope) -> bool {
+    attrs
+        .iter()
+        .any(|attr| scope.resolve_name(attr.path()) == "task")
+}
+
+/// The first generic type of the last segment of a path, like `T` of `Res<T>`.
+fn get_first_type(path: &syn::Path) -> Option<&Type> {
+    if let PathArguments::AngleBracketed(generic_args) = &path.segments.last()?.arguments {
+        if let Some(GenericArgument::Type(inner_type)) = generic_args.args.first() {
+            return Some(inner_type);
         }
     }
-    false
+    None
 }
 
-pub fn get_task_input(token_stream: Punctuated<FnArg, Comma>) -> Vec<TaskInput> {
+fn get_member_type(elem: &Type, scope: &FileScope) -> MemberType {
+    match elem {
+        Type::Path(type_path) if scope.resolve_name(&type_path.path) == "Option" => {
+            if let Some(inner_type) = get_first_type(&type_path.path) {
+                return MemberType::Optional(Box::new(get_member_type(inner_type, scope)));
+            }
+            MemberType::Normal(scope.resolve_type(elem))
+        }
+        Type::Reference(reference) => match &*reference.elem {
+            Type::TraitObject(trait_object) => {
+                let name = match trait_object.bounds.first() {
+                    Some(TypeParamBound::Trait(bound)) => {
+                        scope.resolve_path(&bound.path).join("::")
+                    }
+                    _ => scope.resolve_type(&reference.elem),
+                };
+                if reference.mutability.is_some() {
+                    MemberType::TraitMut(name)
+                } else {
+                    MemberType::Trait(name)
+                }
+            }
+            elem => MemberType::Normal(scope.resolve_type(elem)),
+        },
+        elem => MemberType::Normal(scope.resolve_type(elem)),
+    }
+}
+
+fn get_arch_filter(filter: &Type, scope: &FileScope) -> Option<ArchFilter> {
+    if let Type::Path(type_path) = filter {
+        let inner = scope.resolve_type(get_first_type(&type_path.path)?);
+        return match scope.resolve_name(&type_path.path).as_str() {
+            "With" => Some(ArchFilter::With(inner)),
+            "Without" => Some(ArchFilter::Without(inner)),
+            "Changed" => Some(ArchFilter::Changed(inner)),
+            "Added" => Some(ArchFilter::Added(inner)),
+            _ => None,
+        };
+    }
+    None
+}
+
+/// Reads the inputs of a task.
+/// Wrappers like `Res` are matched by the name their path resolves to in the file,
+/// and the types in them are written as canonical paths, see `FileScope::resolve_type`.
+pub fn get_task_input(token_stream: Punctuated<FnArg, Comma>, scope: &FileScope) -> Vec<TaskInput> {
     let mut inputs: Vec<TaskInput> = Vec::new();
 
     for input in token_stream.iter() {
@@ -202,116 +369,101 @@ pub fn get_task_input(token_stream: Punctuated<FnArg, Comma>) -> Vec<TaskInput>
                 _ => "_".to_string(),
             };
 
-            let ty = &*pat_type.ty;
-            if let Type::Path(type_path) = ty {
-                if let Some(segment) = type_path.path.segments.last() {
-                    if let PathArguments::AngleBracketed(generic_args) = &segment.arguments {
-                        if let Some(GenericArgument::Type(inner_type)) = generic_args.args.first() {
-                            if segment.ident == "Arch" {
-                                if let Type::Tuple(TypeTuple { elems, .. }) = inner_type {
-                                    let elems: Vec<MemberType> = elems
-                                        .iter()
-                                        .map(|elem| {
-                                            let val = elem.to_token_stream().to_string();
-                                            if val.starts_with("& dyn ") {
-                                                MemberType::Trait(
-                                                    val.replace("dyn ", "")
-                                                        .replace(" ", "")
-                                                        .replace("&", ""),
-                                                )
-                                            } else {
-                                                MemberType::Normal(
-                                                    val.replace(" ", "").replace("&", ""),
-                                                )
-                                            }
-                                        })
-                                        .collect();
-                                    inputs.push(TaskInput::Arch(name, elems));
-                                } else {
-                                    let val = inner_type.to_token_stream().to_string();
-                                    let new_val: MemberType;
-                                    if val.starts_with("& dyn ") {
-                                        new_val = MemberType::Trait(
-                                            val.replace("dyn ", "")
-                                                .replace(" ", "")
-                                                .replace("&", ""),
-                                        )
-                                    } else {
-                                        new_val = MemberType::Normal(
-                                            val.replace(" ", "").replace("&", ""),
-                                        )
-                                    }
-                                    inputs.push(TaskInput::Arch(name, vec![new_val]));
+            let Type::Path(type_path) = &*pat_type.ty else {
+                continue;
+            };
+            let wrapper = scope.resolve_name(&type_path.path);
+            if let Some(inner_type) = get_first_type(&type_path.path) {
+                if wrapper == "Arch" {
+                    let filters = match &type_path.path.segments.last().unwrap().arguments {
+                        PathArguments::AngleBracketed(generic_args) => {
+                            match generic_args.args.iter().nth(1) {
+                                Some(GenericArgument::Type(Type::Tuple(TypeTuple {
+                                    elems,
+                                    ..
+                                }))) => elems
+                                    .iter()
+                                    .filter_map(|filter| get_arch_filter(filter, scope))
+                                    .collect(),
+                                Some(GenericArgument::Type(filter)) => {
+                                    get_arch_filter(filter, scope).into_iter().collect()
                                 }
-                                continue;
-                            }
-
-                            if segment.ident == "Res" {
-                                inputs.push(TaskInput::Resources(
-                                    name,
-                                    inner_type.to_token_stream().to_string().replace(" ", ""),
-                                ));
-                                continue;
-                            }
-
-                            if segment.ident == "State" {
-                                inputs.push(TaskInput::State(
-                                    name,
-                                    inner_type.to_token_stream().to_string().replace(" ", ""),
-                                ));
-                                continue;
-                            }
-                            if segment.ident == "Hierarchy" {
-                                inputs.push(TaskInput::Hierarchy(
-                                    name,
-                                    inner_type.to_token_stream().to_string().replace(" ", ""),
-                                ));
-                                continue;
+                                _ => vec![],
                             }
                         }
+                        _ => vec![],
+                    };
+                    if let Type::Tuple(TypeTuple { elems, .. }) = inner_type {
+                        let elems: Vec<MemberType> = elems
+                            .iter()
+                            .map(|elem| get_member_type(elem, scope))
+                            .collect();
+                        inputs.push(TaskInput::Arch(name, elems, filters));
+                    } else {
+                        inputs.push(TaskInput::Arch(
+                            name,
+                            vec![get_member_type(inner_type, scope)],
+                            filters,
+                        ));
                     }
-                }
-                if type_path.to_token_stream().to_string() == "DeltaTime" {
-                    inputs.push(TaskInput::DeltaTime(name));
                     continue;
                 }
+
+                let inner = scope.resolve_type(inner_type);
+                match wrapper.as_str() {
+                    "Res" => inputs.push(TaskInput::Resources(name, inner)),
+                    "ResMut" => inputs.push(TaskInput::ResourcesMut(name, inner)),
+                    "State" => inputs.push(TaskInput::State(name, inner)),
+                    "StateMut" => inputs.push(TaskInput::StateMut(name, inner)),
+                    "Hierarchy" => inputs.push(TaskInput::Hierarchy(name, inner)),
+                    "EventReader" => inputs.push(TaskInput::EventReader(name, inner)),
+                    "Removed" => inputs.push(TaskInput::Removed(name, inner)),
+                    "EventWriter" => inputs.push(TaskInput::EventWriter(name, inner)),
+                    _ => {}
+                }
+                continue;
+            }
+            match wrapper.as_str() {
+                "World" => inputs.push(TaskInput::World(name)),
+                "Commands" => inputs.push(TaskInput::Commands(name)),
+                "DeltaTime" => inputs.push(TaskInput::DeltaTime(name)),
+                "FixedDeltaTime" => inputs.push(TaskInput::FixedDeltaTime(name)),
+                "ElapsedTime" => inputs.push(TaskInput::ElapsedTime(name)),
+                "FrameCount" => inputs.push(TaskInput::FrameCount(name)),
+                _ => {}
             }
         }
     }
     inputs
 }
 
-fn get_task_output(return_type: ReturnType) -> Vec<TaskOutput> {
+fn get_task_output(return_type: ReturnType, scope: &FileScope) -> Vec<TaskOutput> {
     let mut outputs: Vec<TaskOutput> = Vec::new();
 
     if let ReturnType::Type(_, t) = return_type {
         if let Type::Tuple(t) = *t {
             for elem in t.elems {
                 if let Type::Path(t) = elem {
-                    for segment in t.path.segments {
-                        match segment.ident.to_string().as_str() {
-                            "RArch" => {
-                                if let PathArguments::AngleBracketed(a) = segment.arguments {
-                                    for arg in a.args {
-                                        if let GenericArgument::Type(Type::Tuple(elem)) = arg {
-                                            outputs.push(TaskOutput::Arch(
-                                                elem.elems
-                                                    .iter()
-                                                    .map(|x| {
-                                                        x.to_token_stream()
-                                                            .to_string()
-                                                            .replace(" ", "")
-                                                    })
-                                                    .collect::<Vec<String>>(),
-                                            ));
-                                        }
-                                    }
-                                }
+                    match scope.resolve_name(&t.path).as_str() {
+                        "RArch" => {
+                            if let Some(Type::Tuple(elem)) = get_first_type(&t.path) {
+                                outputs.push(TaskOutput::Arch(
+                                    elem.elems
+                                        .iter()
+                                        .map(|x| scope.resolve_type(x))
+                                        .collect::<Vec<String>>(),
+                                ));
+                            }
+                        }
+                        "Events" => {
+                            if let Some(t) = get_first_type(&t.path) {
+                                outputs.push(TaskOutput::Events(scope.resolve_type(t)));
                             }
-                            "Signal" => outputs.push(TaskOutput::Signal),
-                            "Reset" => outputs.push(TaskOutput::Reset),
-                            _ => {}
                         }
+                        "Signal" => outputs.push(TaskOutput::Signal),
+                        "Reset" => outputs.push(TaskOutput::Reset),
+                        "Exit" => outputs.push(TaskOutput::Exit),
+                        _ => {}
                     }
                 }
             }
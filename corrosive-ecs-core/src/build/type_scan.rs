@@ -0,0 +1,588 @@
+use crate::build::components_scan::ComponentMap;
+use quote::ToTokens;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use syn::{
+    File, GenericArgument, Item, ItemType, PathArguments, Type, TypeParamBound, TypePath, UseTree,
+};
+
+/// How many type aliases are followed while resolving a single type.
+const MAX_ALIAS_DEPTH: usize = 8;
+
+/// Writes types as compact strings, like `Locked<Position>` or `(A,B)`.
+/// The paths in them are written by the implementor.
+trait TypeWriter {
+    /// Writes the path of a type, without its generic arguments.
+    fn write_path(&self, path: &syn::Path, out: &mut String);
+
+    /// Writes the path of a type along with the generic arguments of its last segment.
+    fn write_type_path(&self, path: &syn::Path, out: &mut String) {
+        self.write_path(path, out);
+        if let Some(segment) = path.segments.last() {
+            self.write_arguments(&segment.arguments, out);
+        }
+    }
+
+    fn write_type(&self, ty: &Type, out: &mut String) {
+        match ty {
+            Type::Path(TypePath { qself: None, path }) => self.write_type_path(path, out),
+            Type::Reference(reference) => {
+                out.push('&');
+                if let Some(lifetime) = &reference.lifetime {
+                    out.push_str(&lifetime.to_string());
+                    out.push(' ');
+                }
+                if reference.mutability.is_some() {
+                    out.push_str("mut ");
+                }
+                self.write_type(&reference.elem, out);
+            }
+            Type::TraitObject(trait_object) => {
+                out.push_str("dyn ");
+                for (i, bound) in trait_object.bounds.iter().enumerate() {
+                    if i > 0 {
+                        out.push('+');
+                    }
+                    match bound {
+                        TypeParamBound::Trait(bound) => {
+                            self.write_path(&bound.path, out);
+                            if let Some(segment) = bound.path.segments.last() {
+                                self.write_arguments(&segment.arguments, out);
+                            }
+                        }
+                        bound => out.push_str(&compact(bound)),
+                    }
+                }
+            }
+            Type::Tuple(tuple) => {
+                out.push('(');
+                for (i, elem) in tuple.elems.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    self.write_type(elem, out);
+                }
+                if tuple.elems.len() == 1 {
+                    out.push(',');
+                }
+                out.push(')');
+            }
+            Type::Paren(paren) => self.write_type(&paren.elem, out),
+            Type::Group(group) => self.write_type(&group.elem, out),
+            ty => out.push_str(&compact(ty)),
+        }
+    }
+
+    fn write_arguments(&self, arguments: &PathArguments, out: &mut String) {
+        if let PathArguments::AngleBracketed(arguments) = arguments {
+            out.push('<');
+            for (i, argument) in arguments.args.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                match argument {
+                    GenericArgument::Type(ty) => self.write_type(ty, out),
+                    argument => out.push_str(&compact(argument)),
+                }
+            }
+            out.push('>');
+        }
+    }
+}
+
+fn compact(tokens: &impl ToTokens) -> String {
+    tokens.to_token_stream().to_string().replace(" ", "")
+}
+
+/// The names brought into scope by a scanned file, used to turn the types it names into canonical paths.
+/// Canonical paths start with `crate` for items of the scanned crate, like `crate::comp::Position`,
+/// and with the name of the crate otherwise.
+/// Paths that can not be resolved, like the ones of glob imports, are kept as written.
+pub struct FileScope {
+    module: Vec<String>,
+    imports: HashMap<String, Vec<String>>,
+    glob_imports: Vec<Vec<String>>,
+    aliases: HashMap<String, Type>,
+    locals: HashSet<String>,
+    alias_depth: Cell<usize>,
+}
+
+impl FileScope {
+    /// Collects the `use` items, type aliases and items of a file in the module, like `["crate", "task"]`.
+    pub fn new(file: &File, module: Vec<String>) -> Self {
+        let mut scope = FileScope {
+            module,
+            imports: HashMap::new(),
+            glob_imports: Vec::new(),
+            aliases: HashMap::new(),
+            locals: HashSet::new(),
+            alias_depth: Cell::new(0),
+        };
+        let mut imports = Vec::new();
+        for item in &file.items {
+            match item {
+                Item::Use(item) => collect_imports(&item.tree, Vec::new(), &mut imports),
+                Item::Type(ItemType {
+                    ident,
+                    generics,
+                    ty,
+                    ..
+                }) if generics.params.is_empty() => {
+                    scope.aliases.insert(ident.to_string(), (**ty).clone());
+                }
+                Item::Struct(item) => {
+                    scope.locals.insert(item.ident.to_string());
+                }
+                Item::Enum(item) => {
+                    scope.locals.insert(item.ident.to_string());
+                }
+                Item::Trait(item) => {
+                    scope.locals.insert(item.ident.to_string());
+                }
+                Item::Mod(item) => {
+                    scope.locals.insert(item.ident.to_string());
+                }
+                _ => {}
+            }
+        }
+        for (name, path) in imports {
+            let path = scope.resolve_segments(path);
+            if name == "*" {
+                scope.glob_imports.push(path);
+            } else {
+                scope.imports.insert(name, path);
+            }
+        }
+        scope
+    }
+
+    /// The canonical paths of the modules the file imports with a glob, like `crate::comp` for `use crate::comp::*;`.
+    pub fn glob_imports(&self) -> Vec<String> {
+        self.glob_imports
+            .iter()
+            .map(|path| path.join("::"))
+            .collect()
+    }
+
+    /// Module of the `mod.rs` file in a scanned directory.
+    /// The root directory of a scan is a module of the crate, like `crate::comp`.
+    pub fn module_of(parent: &[String], directory: &Path) -> Vec<String> {
+        let mut module = parent.to_vec();
+        if let Some(name) = directory.file_name().and_then(|name| name.to_str()) {
+            module.push(name.to_string());
+        }
+        module
+    }
+
+    /// The canonical segments of a path, without generic arguments.
+    pub fn resolve_path(&self, path: &syn::Path) -> Vec<String> {
+        let segments = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<String>>();
+        if path.leading_colon.is_some() {
+            return segments;
+        }
+        self.resolve_segments(segments)
+    }
+
+    /// The last segment of the canonical path, which is the name of the item the path points to.
+    pub fn resolve_name(&self, path: &syn::Path) -> String {
+        self.resolve_path(path).pop().unwrap_or_default()
+    }
+
+    /// The type with its paths made canonical and its aliases replaced, like `Locked<crate::comp::Position>`.
+    pub fn resolve_type(&self, ty: &Type) -> String {
+        let mut out = String::new();
+        self.write_type(ty, &mut out);
+        out
+    }
+
+    fn resolve_segments(&self, segments: Vec<String>) -> Vec<String> {
+        let Some(first) = segments.first() else {
+            return segments;
+        };
+        match first.as_str() {
+            "crate" => segments,
+            "self" => [self.module.clone(), segments[1..].to_vec()].concat(),
+            "super" => {
+                let mut module = self.module.clone();
+                let mut rest = segments.as_slice();
+                while rest.first().is_some_and(|segment| segment == "super") {
+                    module.pop();
+                    rest = &rest[1..];
+                }
+                [module, rest.to_vec()].concat()
+            }
+            first => {
+                if let Some(import) = self.imports.get(first) {
+                    [import.clone(), segments[1..].to_vec()].concat()
+                } else if self.locals.contains(first) {
+                    [self.module.clone(), segments].concat()
+                } else {
+                    segments
+                }
+            }
+        }
+    }
+
+    fn alias(&self, path: &syn::Path) -> Option<&Type> {
+        if path.leading_colon.is_some() || path.segments.len() != 1 {
+            return None;
+        }
+        self.aliases.get(&path.segments[0].ident.to_string())
+    }
+}
+
+impl TypeWriter for FileScope {
+    fn write_path(&self, path: &syn::Path, out: &mut String) {
+        out.push_str(&self.resolve_path(path).join("::"));
+    }
+
+    fn write_type_path(&self, path: &syn::Path, out: &mut String) {
+        let depth = self.alias_depth.get();
+        match self.alias(path) {
+            Some(alias) if depth < MAX_ALIAS_DEPTH => {
+                self.alias_depth.set(depth + 1);
+                self.write_type(alias, out);
+                self.alias_depth.set(depth);
+            }
+            _ => {
+                self.write_path(path, out);
+                if let Some(segment) = path.segments.last() {
+                    self.write_arguments(&segment.arguments, out);
+                }
+            }
+        }
+    }
+}
+
+fn collect_imports(
+    tree: &UseTree,
+    mut prefix: Vec<String>,
+    imports: &mut Vec<(String, Vec<String>)>,
+) {
+    match tree {
+        UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            collect_imports(&path.tree, prefix, imports);
+        }
+        UseTree::Name(name) if name.ident == "self" => {
+            if let Some(last) = prefix.last() {
+                imports.push((last.clone(), prefix));
+            }
+        }
+        UseTree::Name(name) => {
+            let name = name.ident.to_string();
+            prefix.push(name.clone());
+            imports.push((name, prefix));
+        }
+        UseTree::Rename(rename) => {
+            if rename.ident != "self" {
+                prefix.push(rename.ident.to_string());
+            }
+            imports.push((rename.rename.to_string(), prefix));
+        }
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_imports(tree, prefix.clone(), imports);
+            }
+        }
+        UseTree::Glob(_) => imports.push(("*".to_string(), prefix)),
+    }
+}
+
+/// Maps the canonical paths of the components of the app and its packages to the names the engine uses for them.
+/// Components are known by their path, like `crate::comp::Position` or `corrosive_2d::comp::Position2D`.
+/// The engine writes a component by its name when no other component shares it and by its path otherwise.
+pub struct ComponentNames {
+    paths: HashMap<String, String>,
+}
+
+impl ComponentNames {
+    /// Takes the component maps along with their crates, `crate` for the app and the package name otherwise.
+    pub fn new(component_maps: &[(ComponentMap, String)]) -> Self {
+        let mut components: Vec<(String, String)> = Vec::new();
+        for (component_map, crate_name) in component_maps {
+            let crate_name = crate_name.replace("-", "_");
+            for (name, path) in component_map.get_all_paths() {
+                components.push((name, format!("{}::{}", crate_name, path)));
+            }
+        }
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (name, _) in &components {
+            *counts.entry(name).or_default() += 1;
+        }
+        let paths = components
+            .iter()
+            .map(|(name, path)| {
+                let engine_name = if counts[name.as_str()] == 1 {
+                    name.clone()
+                } else {
+                    path.clone()
+                };
+                (path.clone(), engine_name)
+            })
+            .collect();
+        ComponentNames { paths }
+    }
+
+    /// Returns the name the engine uses for the component at the path,
+    /// which is the path itself should another component share its name.
+    pub fn get(&self, path: &str) -> Option<&String> {
+        self.paths.get(path)
+    }
+
+    /// Turns a type scanned in the crate, as written by `FileScope::resolve_type`, into the type the engine uses.
+    /// Components become their engine name, like `Locked<Position>`, and the types of the core crate lose their path.
+    /// Paths that point to no known component are looked up in the glob imports of the file and are kept otherwise,
+    /// single names left over are taken as the name of a component.
+    pub fn engine_type(&self, ty: &str, crate_name: &str, glob_imports: &[String]) -> String {
+        let Ok(parsed) = syn::parse_str::<Type>(ty) else {
+            return ty.to_string();
+        };
+        let mut out = String::new();
+        let crate_name = crate_name.replace("-", "_");
+        EngineTypeWriter {
+            names: self,
+            glob_imports: glob_imports
+                .iter()
+                .map(|path| match path.strip_prefix("crate::") {
+                    Some(rest) => format!("{}::{}", crate_name, rest),
+                    None => path.clone(),
+                })
+                .collect(),
+            crate_name,
+        }
+        .write_type(&parsed, &mut out);
+        out
+    }
+}
+
+struct EngineTypeWriter<'a> {
+    names: &'a ComponentNames,
+    crate_name: String,
+    glob_imports: Vec<String>,
+}
+
+impl TypeWriter for EngineTypeWriter<'_> {
+    fn write_path(&self, path: &syn::Path, out: &mut String) {
+        let mut segments = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<String>>();
+        if segments.len() > 1 && segments[0] == "crate" {
+            segments[0] = self.crate_name.clone();
+        }
+        let last = segments.last().cloned().unwrap_or_default();
+        let path = segments.join("::");
+        let glob_import = self
+            .glob_imports
+            .iter()
+            .find_map(|module| self.names.paths.get(&format!("{}::{}", module, path)));
+        if let Some(name) = self.names.paths.get(&path) {
+            out.push_str(name);
+        } else if let Some(name) = glob_import {
+            out.push_str(name);
+        } else if segments.len() == 1 || segments[0] == "corrosive_ecs_core" {
+            out.push_str(&last);
+        } else {
+            out.push_str(&segments.join("::"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::components_scan::ComponentType;
+    use std::path::PathBuf;
+
+    fn scope() -> FileScope {
+        let file: File = syn::parse_str(
+            r#"
+            use crate::comp::Position as Pos;
+            use super::helpers;
+            use super::shared::*;
+            use self::local::Thing;
+            use corrosive_ecs_core::ecs_core::{Locked, Res as R};
+            type Speed = crate::comp::Velocity;
+            type Moving = (Locked<Pos>, Speed);
+            type Loop = Again;
+            type Again = Loop;
+            pub struct Marker;
+            "#,
+        )
+        .unwrap();
+        FileScope::new(
+            &file,
+            vec!["crate".to_string(), "task".to_string(), "sub".to_string()],
+        )
+    }
+
+    fn resolve(ty: &str) -> String {
+        scope().resolve_type(&syn::parse_str(ty).unwrap())
+    }
+
+    #[test]
+    fn resolves_renames_and_aliases() {
+        assert_eq!(
+            resolve("Locked<Pos>"),
+            "corrosive_ecs_core::ecs_core::Locked<crate::comp::Position>"
+        );
+        assert_eq!(
+            resolve("R<Speed>"),
+            "corrosive_ecs_core::ecs_core::Res<crate::comp::Velocity>"
+        );
+        assert_eq!(
+            resolve("Moving"),
+            "(corrosive_ecs_core::ecs_core::Locked<crate::comp::Position>,crate::comp::Velocity)"
+        );
+        assert_eq!(resolve("Loop").matches("::").count(), 0);
+    }
+
+    #[test]
+    fn resolves_self_and_super() {
+        assert_eq!(resolve("self::Marker"), "crate::task::sub::Marker");
+        assert_eq!(resolve("Marker"), "crate::task::sub::Marker");
+        assert_eq!(resolve("Thing"), "crate::task::sub::local::Thing");
+        assert_eq!(resolve("super::Counter"), "crate::task::Counter");
+        assert_eq!(resolve("super::super::comp::Hp"), "crate::comp::Hp");
+        assert_eq!(resolve("helpers::Tool"), "crate::task::helpers::Tool");
+    }
+
+    #[test]
+    fn keeps_unresolved_paths() {
+        assert_eq!(resolve("Unknown"), "Unknown");
+        assert_eq!(resolve("::glam::Vec2"), "glam::Vec2");
+        assert_eq!(resolve("&mut dyn Pos"), "&mut dyn crate::comp::Position");
+    }
+
+    #[test]
+    fn collects_glob_imports() {
+        assert_eq!(
+            scope().glob_imports(),
+            vec!["crate::task::shared".to_string()]
+        );
+    }
+
+    fn component_map(path: &str, components: &[&str], sub_maps: Vec<ComponentMap>) -> ComponentMap {
+        ComponentMap {
+            path: PathBuf::from(path),
+            sub_maps,
+            components: components
+                .iter()
+                .map(|x| ComponentType::Component(x.to_string()))
+                .collect(),
+        }
+    }
+
+    fn names() -> ComponentNames {
+        ComponentNames::new(&[
+            (
+                component_map(
+                    "./src/comp",
+                    &["Position"],
+                    vec![component_map("./src/comp/sub", &["Velocity"], vec![])],
+                ),
+                "crate".to_string(),
+            ),
+            (
+                component_map("./src/comp", &["Sprite2D"], vec![]),
+                "corrosive-2d".to_string(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn engine_types_name_components() {
+        let names = names();
+        assert_eq!(
+            names.engine_type(
+                "corrosive_ecs_core::ecs_core::Locked<crate::comp::Position>",
+                "crate",
+                &[]
+            ),
+            "Locked<Position>"
+        );
+        assert_eq!(
+            names.engine_type("crate::comp::sub::Velocity", "crate", &[]),
+            "Velocity"
+        );
+        assert_eq!(
+            names.engine_type("crate::comp::Sprite2D", "corrosive-2d", &[]),
+            "Sprite2D"
+        );
+        assert_eq!(
+            names.engine_type("Member<corrosive_2d::comp::Sprite2D>", "crate", &[]),
+            "Member<Sprite2D>"
+        );
+        assert_eq!(names.engine_type("Position", "crate", &[]), "Position");
+    }
+
+    #[test]
+    fn engine_types_keep_unknown_paths() {
+        let names = names();
+        assert_eq!(
+            names.engine_type("other_crate::Position", "crate", &[]),
+            "other_crate::Position"
+        );
+        assert_eq!(
+            names.engine_type("crate::comp::a::Position", "crate", &[]),
+            "crate::comp::a::Position"
+        );
+        assert_eq!(
+            names.engine_type("crate::comp::Other", "corrosive-2d", &[]),
+            "corrosive_2d::comp::Other"
+        );
+    }
+
+    #[test]
+    fn shared_names_are_written_by_path() {
+        let names = ComponentNames::new(&[
+            (
+                component_map(
+                    "./src/comp",
+                    &["Position", "Velocity"],
+                    vec![component_map("./src/comp/a", &["Position"], vec![])],
+                ),
+                "crate".to_string(),
+            ),
+            (
+                component_map("./src/comp", &["Position"], vec![]),
+                "corrosive-2d".to_string(),
+            ),
+        ]);
+        assert_eq!(
+            names.engine_type("Locked<crate::comp::Position>", "crate", &[]),
+            "Locked<crate::comp::Position>"
+        );
+        assert_eq!(
+            names.engine_type("crate::comp::a::Position", "crate", &[]),
+            "crate::comp::a::Position"
+        );
+        assert_eq!(
+            names.engine_type("crate::comp::Position", "corrosive-2d", &[]),
+            "corrosive_2d::comp::Position"
+        );
+        assert_eq!(
+            names.engine_type("crate::comp::Velocity", "crate", &[]),
+            "Velocity"
+        );
+        assert_eq!(
+            names.engine_type("Position", "crate", &["crate::comp::a".to_string()]),
+            "crate::comp::a::Position"
+        );
+        assert_eq!(
+            names.engine_type("Position", "corrosive-2d", &["crate::comp".to_string()]),
+            "corrosive_2d::comp::Position"
+        );
+        assert_eq!(
+            names.engine_type("a::Position", "crate", &["crate::comp".to_string()]),
+            "crate::comp::a::Position"
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static CHANGE_TICK: AtomicU64 = AtomicU64::new(1);
+
+/// Returns the current change tick.
+pub fn change_tick() -> u64 {
+    CHANGE_TICK.load(Ordering::SeqCst)
+}
+/// Used by engine when a task with `Changed<T>` or `Added<T>` filters runs.
+/// Returns the tick the task runs at and advances the change tick,
+/// so changes made during the run are newer than the task.
+pub fn next_change_tick() -> u64 {
+    CHANGE_TICK.fetch_add(1, Ordering::SeqCst)
+}
+
+/// The ticks at which a component was added to its archetype and last written to.
+#[derive(Debug)]
+pub struct ChangeTicks {
+    added: AtomicU64,
+    changed: AtomicU64,
+}
+impl Default for ChangeTicks {
+    fn default() -> Self {
+        let tick = change_tick();
+        ChangeTicks {
+            added: AtomicU64::new(tick),
+            changed: AtomicU64::new(tick),
+        }
+    }
+}
+impl ChangeTicks {
+    /// Returns the tick at which the component was added.
+    pub fn added(&self) -> u64 {
+        self.added.load(Ordering::SeqCst)
+    }
+    /// Returns the tick at which the component was last written to.
+    pub fn changed(&self) -> u64 {
+        self.changed.load(Ordering::SeqCst)
+    }
+    /// Marks the component as written to.
+    pub fn set_changed(&self) {
+        self.changed.store(change_tick(), Ordering::SeqCst);
+    }
+    /// Used by engine to mark the component as added, which counts as a change as well.
+    pub fn set_added(&self) {
+        let tick = change_tick();
+        self.added.store(tick, Ordering::SeqCst);
+        self.changed.store(tick, Ordering::SeqCst);
+    }
+}
+
+/// Implemented for components that track their changes.
+/// Used by the `Changed<T>` and `Added<T>` filters of `Arch`.
+pub trait Tracked {
+    fn ticks(&self) -> &ChangeTicks;
+}
@@ -0,0 +1,134 @@
+use crate::entity::Entity;
+use std::any::{Any, TypeId};
+use std::sync::Mutex;
+
+/// Components of an entity while it is moved between archetypes.
+pub type ComponentParts = Vec<(TypeId, Box<dyn Any + Send + Sync>)>;
+
+/// A change to the world that is applied by the engine in the merge phase.
+pub enum Command {
+    Spawn(Entity, ComponentParts),
+    Despawn(Entity),
+    InsertComponent(Entity, TypeId, Box<dyn Any + Send + Sync>),
+    RemoveComponent(Entity, TypeId),
+    InsertResource(TypeId, Box<dyn Any + Send + Sync>),
+}
+
+/// Used by engine to hold the commands queued by tasks until the merge phase.
+#[derive(Default)]
+pub struct CommandQueue {
+    commands: Mutex<Vec<Command>>,
+}
+impl CommandQueue {
+    /// Queues a command.
+    pub fn push(&self, command: Command) {
+        self.commands
+            .lock()
+            .expect("Failed to lock the command queue")
+            .push(command);
+    }
+    /// Takes every queued command.
+    pub fn take(&self) -> Vec<Command> {
+        std::mem::take(
+            &mut *self
+                .commands
+                .lock()
+                .expect("Failed to lock the command queue"),
+        )
+    }
+}
+
+/// Used as an input for tasks to queue changes to the world.
+/// Can be copied into helper functions and closures.
+/// Commands are applied in the merge phase, in the order they were queued.
+///
+/// Example:
+/// ```ignore
+/// #[task]
+/// pub fn spawner(commands: Commands) {
+///     let entity = commands
+///         .spawn()
+///         .with(Locked::new(Position { x: 0.0, y: 0.0 }))
+///         .with(Velocity { x: 1.0, y: 0.0 })
+///         .build();
+///     commands.despawn(entity);
+/// }
+/// ```
+#[derive(Clone, Copy)]
+pub struct Commands<'a> {
+    commands: &'a CommandQueue,
+}
+impl<'a> Commands<'a> {
+    pub fn new(commands: &'a CommandQueue) -> Self {
+        Commands { commands }
+    }
+    /// Starts spawning an entity.
+    /// The components must match an archetype that some task outputs.
+    /// Otherwise the engine panics in debug builds and frees the entity in release builds.
+    pub fn spawn(&self) -> SpawnBuilder<'a> {
+        SpawnBuilder {
+            commands: self.commands,
+            parts: Vec::new(),
+        }
+    }
+    /// Removes an entity and expires its `Ref`, `LockedRef` and `Member` components.
+    pub fn despawn(&self, entity: Entity) {
+        self.commands.push(Command::Despawn(entity));
+    }
+    /// Adds a component to an entity, or replaces it should the entity already have one.
+    /// The new components of the entity must match an archetype, the same as with `spawn`.
+    pub fn insert_component<T: Any + Send + Sync>(&self, entity: Entity, component: T) {
+        self.commands.push(Command::InsertComponent(
+            entity,
+            TypeId::of::<T>(),
+            Box::new(component),
+        ));
+    }
+    /// Removes a component from an entity.
+    pub fn remove_component<T: Any + Send + Sync>(&self, entity: Entity) {
+        self.commands
+            .push(Command::RemoveComponent(entity, TypeId::of::<T>()));
+    }
+    /// Replaces the value of a resource.
+    /// Ignored if no task uses the resource.
+    pub fn insert_resource<T: Any + Send + Sync>(&self, resource: T) {
+        self.commands.push(Command::InsertResource(
+            TypeId::of::<T>(),
+            Box::new(resource),
+        ));
+    }
+}
+
+/// Collects the components of an entity queued with `Commands::spawn`.
+pub struct SpawnBuilder<'a> {
+    commands: &'a CommandQueue,
+    parts: ComponentParts,
+}
+impl SpawnBuilder<'_> {
+    /// Adds a component to the entity.
+    pub fn with<T: Any + Send + Sync>(mut self, component: T) -> Self {
+        self.parts.push((TypeId::of::<T>(), Box::new(component)));
+        self
+    }
+    /// Queues the entity and returns it.
+    /// The entity can be used in other commands before it is spawned.
+    pub fn build(self) -> Entity {
+        let entity = Entity::allocate();
+        self.commands.push(Command::Spawn(entity, self.parts));
+        entity
+    }
+}
+
+/// Used by engine to take a component out of the parts of an entity.
+/// Panics if the parts do not have the component.
+pub fn take_component<T: 'static>(parts: &mut ComponentParts) -> T {
+    let index = parts
+        .iter()
+        .position(|(type_id, _)| *type_id == TypeId::of::<T>())
+        .expect("Component is missing from the entity");
+    *parts
+        .swap_remove(index)
+        .1
+        .downcast::<T>()
+        .expect("Component has the wrong type")
+}
@@ -0,0 +1,34 @@
+use std::any::{type_name, Any, TypeId};
+
+/// Returns the concrete type of a component behind a trait object.
+/// Implemented for every type, add it as a supertrait of a `trait_bound` trait to use it on `&dyn Trait` members.
+///
+/// Example:
+/// ```ignore
+/// #[trait_bound]
+/// pub trait Damageable: ComponentInfo {
+///     fn damage(&mut self, amount: f32);
+/// }
+///
+/// #[task]
+/// pub fn explode(targets: Arch<(&mut dyn Damageable,)>) {
+///     for (mut target,) in targets.iter() {
+///         target.damage(10.0);
+///         println!("damaged {}", target.type_name());
+///     }
+/// }
+/// ```
+pub trait ComponentInfo {
+    /// Returns the name of the concrete type.
+    fn type_name(&self) -> &'static str;
+    /// Returns the TypeId of the concrete type.
+    fn component_type_id(&self) -> TypeId;
+}
+impl<T: Any> ComponentInfo for T {
+    fn type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+    fn component_type_id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+}
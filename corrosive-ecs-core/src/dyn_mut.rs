@@ -0,0 +1,78 @@
+use crate::component_info::ComponentInfo;
+use std::any::TypeId;
+use std::ops::{Deref, DerefMut};
+use std::sync::RwLockWriteGuard;
+
+/// Yielded by `Arch` for `&mut dyn Trait` members.
+/// `&mut dyn Trait` matches the components registered for the trait with `trait_for!` that are stored in `Locked`.
+/// Holds the write lock of the component, which marks it as changed, until it is dropped.
+///
+/// Example:
+/// ```ignore
+/// #[task]
+/// pub fn poison(targets: Arch<(&mut dyn Damageable,)>) {
+///     for (mut target,) in targets.iter() {
+///         target.damage(1.0);
+///     }
+/// }
+/// ```
+pub struct DynMut<'a, D: ?Sized> {
+    guard: Box<dyn DerefMut<Target = D> + 'a>,
+}
+impl<'a, D: ?Sized + 'a> DynMut<'a, D> {
+    /// Used by engine to create the item from the write lock of a component.
+    pub fn new<T: 'a>(
+        guard: RwLockWriteGuard<'a, T>,
+        cast: fn(&T) -> &D,
+        cast_mut: fn(&mut T) -> &mut D,
+    ) -> Self {
+        DynMut {
+            guard: Box::new(CastGuard {
+                guard,
+                cast,
+                cast_mut,
+            }),
+        }
+    }
+}
+impl<D: ?Sized + ComponentInfo> DynMut<'_, D> {
+    /// Returns the name of the concrete type of the component.
+    pub fn type_name(&self) -> &'static str {
+        (**self).type_name()
+    }
+    /// Returns the TypeId of the concrete type of the component.
+    pub fn component_type_id(&self) -> TypeId {
+        (**self).component_type_id()
+    }
+}
+impl<D: ?Sized> Deref for DynMut<'_, D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        &self.guard
+    }
+}
+impl<D: ?Sized> DerefMut for DynMut<'_, D> {
+    fn deref_mut(&mut self) -> &mut D {
+        &mut self.guard
+    }
+}
+
+/// Write lock of a component that derefs to a trait object.
+struct CastGuard<'a, T, D: ?Sized> {
+    guard: RwLockWriteGuard<'a, T>,
+    cast: fn(&T) -> &D,
+    cast_mut: fn(&mut T) -> &mut D,
+}
+impl<T, D: ?Sized> Deref for CastGuard<'_, T, D> {
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        (self.cast)(&self.guard)
+    }
+}
+impl<T, D: ?Sized> DerefMut for CastGuard<'_, T, D> {
+    fn deref_mut(&mut self) -> &mut D {
+        (self.cast_mut)(&mut self.guard)
+    }
+}
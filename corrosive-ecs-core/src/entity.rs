@@ -0,0 +1,123 @@
+use std::sync::Mutex;
+
+/// Generational id of an entity.
+/// Assigned when an entity is added to an `RArch` and kept while the entity is alive.
+/// Ids of removed entities are reused with a new generation,
+/// so an old `Entity` never refers to a new entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+struct EntityAllocator {
+    generations: Vec<u32>,
+    free: Vec<u32>,
+}
+
+static ENTITY_ALLOCATOR: Mutex<EntityAllocator> = Mutex::new(EntityAllocator {
+    generations: Vec::new(),
+    free: Vec::new(),
+});
+
+impl EntityAllocator {
+    fn allocate(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => Entity {
+                index,
+                generation: self.generations[index as usize],
+            },
+            None => {
+                self.generations.push(0);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        }
+    }
+    fn free(&mut self, entity: Entity) {
+        if self.is_alive(entity) {
+            self.generations[entity.index as usize] += 1;
+            self.free.push(entity.index);
+        }
+    }
+    fn is_alive(&self, entity: Entity) -> bool {
+        self.generations[entity.index as usize] == entity.generation
+    }
+}
+
+impl Entity {
+    /// Allocates a new entity id.
+    pub fn allocate() -> Entity {
+        ENTITY_ALLOCATOR
+            .lock()
+            .expect("Failed to lock the entity allocator")
+            .allocate()
+    }
+    /// Used by engine to free the id of a removed entity.
+    pub fn free(self) {
+        ENTITY_ALLOCATOR
+            .lock()
+            .expect("Failed to lock the entity allocator")
+            .free(self);
+    }
+    /// Checks if the entity has not been removed.
+    pub fn is_alive(&self) -> bool {
+        ENTITY_ALLOCATOR
+            .lock()
+            .expect("Failed to lock the entity allocator")
+            .is_alive(*self)
+    }
+    /// Index of the entity, shared with older generations.
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+    /// Generation of the entity.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allocator() -> EntityAllocator {
+        EntityAllocator {
+            generations: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn freed_ids_are_reused_with_a_new_generation() {
+        let mut allocator = allocator();
+        let first = allocator.allocate();
+        let second = allocator.allocate();
+        assert_eq!((first.index(), first.generation()), (0, 0));
+        assert_eq!((second.index(), second.generation()), (1, 0));
+
+        allocator.free(first);
+        assert!(!allocator.is_alive(first));
+        assert!(allocator.is_alive(second));
+
+        let reused = allocator.allocate();
+        assert_eq!((reused.index(), reused.generation()), (0, 1));
+        assert_ne!(reused, first);
+        assert!(allocator.is_alive(reused));
+        assert!(!allocator.is_alive(first));
+    }
+
+    #[test]
+    fn freeing_a_stale_entity_does_nothing() {
+        let mut allocator = allocator();
+        let first = allocator.allocate();
+        allocator.free(first);
+        let reused = allocator.allocate();
+
+        allocator.free(first);
+        assert!(allocator.is_alive(reused));
+        assert_eq!(allocator.allocate().index(), 1);
+    }
+}
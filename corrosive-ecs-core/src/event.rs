@@ -0,0 +1,206 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock, RwLockReadGuard};
+
+/// Used by engine to hold the events of a type.
+/// Events sent in a frame become readable in the next frame.
+/// They are kept for two frames, so readers that do not run every frame still receive them.
+pub struct EventChannel<T> {
+    pending: Mutex<Vec<T>>,
+    buffers: RwLock<EventBuffers<T>>,
+}
+/// The readable events of a channel.
+/// Event ids start at `first_id` for `previous` and continue into `current`.
+pub struct EventBuffers<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    first_id: u64,
+}
+impl<T> Default for EventChannel<T> {
+    fn default() -> Self {
+        EventChannel {
+            pending: Mutex::new(Vec::new()),
+            buffers: RwLock::new(EventBuffers {
+                previous: Vec::new(),
+                current: Vec::new(),
+                first_id: 0,
+            }),
+        }
+    }
+}
+impl<T> EventChannel<T> {
+    /// Queues an event for the next frame.
+    pub fn send(&self, event: T) {
+        self.pending
+            .lock()
+            .expect("Failed to lock the event channel")
+            .push(event);
+    }
+    /// Queues events for the next frame.
+    pub fn send_batch(&self, events: Vec<T>) {
+        self.pending
+            .lock()
+            .expect("Failed to lock the event channel")
+            .extend(events);
+    }
+    /// Used by engine to make the events sent this frame readable and drop the oldest ones.
+    pub fn update(&self) {
+        let pending = std::mem::take(
+            &mut *self
+                .pending
+                .lock()
+                .expect("Failed to lock the event channel"),
+        );
+        let mut buffers = self
+            .buffers
+            .write()
+            .expect("Failed to write the event channel");
+        buffers.first_id += buffers.previous.len() as u64;
+        buffers.previous = std::mem::replace(&mut buffers.current, pending);
+    }
+    /// Returns the events after the cursor and moves the cursor past them.
+    pub fn read(&self, cursor: &AtomicU64) -> EventRead<'_, T> {
+        let buffers = self
+            .buffers
+            .read()
+            .expect("Failed to read the event channel");
+        let end = buffers.first_id + (buffers.previous.len() + buffers.current.len()) as u64;
+        let start = cursor.swap(end, Ordering::SeqCst).max(buffers.first_id);
+        EventRead {
+            start: (start.min(end) - buffers.first_id) as usize,
+            buffers,
+        }
+    }
+}
+
+/// Events returned by `EventReader::read`.
+pub struct EventRead<'a, T> {
+    buffers: RwLockReadGuard<'a, EventBuffers<T>>,
+    start: usize,
+}
+impl<T> EventRead<'_, T> {
+    /// Iterates over the events in the order they were sent.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.buffers
+            .previous
+            .iter()
+            .chain(self.buffers.current.iter())
+            .skip(self.start)
+    }
+    /// Returns the number of events.
+    pub fn len(&self) -> usize {
+        self.buffers.previous.len() + self.buffers.current.len() - self.start
+    }
+    /// Returns true should there be no events.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Used as an input for tasks to receive events of a type.
+/// Every reader has its own cursor, so each event is read once by every reader.
+///
+/// Example:
+/// ```ignore
+/// #[task]
+/// pub fn on_hit(hits: EventReader<Hit>) {
+///     for hit in hits.read().iter() {
+///         println!("{}", hit.damage);
+///     }
+/// }
+/// ```
+pub struct EventReader<'a, T> {
+    channel: &'a EventChannel<T>,
+    cursor: &'a AtomicU64,
+}
+impl<'a, T> EventReader<'a, T> {
+    pub fn new(channel: &'a EventChannel<T>, cursor: &'a AtomicU64) -> Self {
+        EventReader { channel, cursor }
+    }
+    /// Returns the events that were not read by this reader yet.
+    pub fn read(&self) -> EventRead<'a, T> {
+        self.channel.read(self.cursor)
+    }
+}
+
+/// Used as an input for tasks to send events of a type.
+/// Events can be read in the next frame.
+pub struct EventWriter<'a, T> {
+    channel: &'a EventChannel<T>,
+}
+impl<'a, T> EventWriter<'a, T> {
+    pub fn new(channel: &'a EventChannel<T>) -> Self {
+        EventWriter { channel }
+    }
+    /// Sends an event.
+    pub fn send(&self, event: T) {
+        self.channel.send(event);
+    }
+}
+
+/// Used as an output for tasks to send events of a type.
+/// Events can be read in the next frame.
+pub struct Events<T> {
+    pub events: Vec<T>,
+}
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Events { events: Vec::new() }
+    }
+}
+impl<T> Events<T> {
+    /// Sends an event.
+    pub fn send(&mut self, event: T) {
+        self.events.push(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(channel: &EventChannel<u32>, cursor: &AtomicU64) -> Vec<u32> {
+        channel.read(cursor).iter().copied().collect()
+    }
+
+    #[test]
+    fn events_are_readable_after_update() {
+        let channel = EventChannel::default();
+        let cursor = AtomicU64::new(0);
+        channel.send(1);
+        assert!(channel.read(&cursor).is_empty());
+        channel.update();
+        assert_eq!(read(&channel, &cursor), vec![1]);
+    }
+
+    #[test]
+    fn every_reader_reads_each_event_once() {
+        let channel = EventChannel::default();
+        let first = AtomicU64::new(0);
+        let second = AtomicU64::new(0);
+        channel.send_batch(vec![1, 2]);
+        channel.update();
+        assert_eq!(read(&channel, &first), vec![1, 2]);
+        assert!(channel.read(&first).is_empty());
+
+        channel.send(3);
+        channel.update();
+        assert_eq!(read(&channel, &first), vec![3]);
+        assert_eq!(channel.read(&second).len(), 3);
+        assert!(channel.read(&second).is_empty());
+    }
+
+    #[test]
+    fn events_are_kept_for_two_frames() {
+        let channel = EventChannel::default();
+        let cursor = AtomicU64::new(0);
+        channel.send(1);
+        channel.update();
+        channel.send(2);
+        channel.update();
+        channel.update();
+        assert_eq!(read(&channel, &cursor), vec![2]);
+        channel.update();
+        channel.update();
+        assert!(channel.read(&cursor).is_empty());
+    }
+}
@@ -0,0 +1,14 @@
+/// Used as an output for tasks.
+/// Should it be triggered, the engine will stop after the current frame and run the shutdown tasks.
+#[derive(Default)]
+pub struct Exit(bool);
+impl Exit {
+    /// Will trigger the exit.
+    pub fn trigger(&mut self) {
+        self.0 = true;
+    }
+    /// Gets the value of the exit type.
+    pub fn get(&self) -> bool {
+        self.0
+    }
+}
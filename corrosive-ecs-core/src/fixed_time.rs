@@ -0,0 +1,75 @@
+/// Settings and state of the fixed update timestep.
+/// Can be used as a task input with `Res<FixedTime>`, or `ResMut<FixedTime>` to change it.
+/// The rate can be set with `fixed_rate` in `corrosive_engine_builder!`.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTime {
+    /// Time between two fixed updates in seconds.
+    pub timestep: f64,
+    /// Maximum number of fixed updates run in a single frame.
+    /// Time that could not be caught up on is dropped.
+    pub max_substeps: u32,
+    /// Time that has not yet been consumed by a fixed update.
+    pub accumulator: f64,
+    /// How far the current frame is between the last and the next fixed update, from 0 to 1.
+    /// Used to interpolate values that are changed in fixed updates.
+    pub alpha: f64,
+}
+impl FixedTime {
+    /// Creates a fixed timestep that runs `rate` times per second.
+    pub fn new(rate: f64) -> FixedTime {
+        FixedTime {
+            timestep: 1.0 / rate,
+            ..Default::default()
+        }
+    }
+
+    /// Used by engine to add the delta time to the accumulator.
+    /// Returns the number of fixed updates that need to run this frame.
+    pub fn advance(&mut self, delta: f64) -> u32 {
+        self.accumulator += delta;
+        let mut steps = 0;
+        while self.accumulator >= self.timestep && steps < self.max_substeps {
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+        if self.accumulator >= self.timestep {
+            self.accumulator %= self.timestep;
+        }
+        self.alpha = self.accumulator / self.timestep;
+        steps
+    }
+}
+impl Default for FixedTime {
+    fn default() -> Self {
+        FixedTime {
+            timestep: 0.1,
+            max_substeps: 5,
+            accumulator: 0.0,
+            alpha: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_one_step_per_timestep() {
+        let mut fixed_time = FixedTime::new(10.0);
+        assert_eq!(fixed_time.advance(0.05), 0);
+        assert!((fixed_time.alpha - 0.5).abs() < 1e-9);
+        assert_eq!(fixed_time.advance(0.26), 3);
+        assert!((fixed_time.accumulator - 0.01).abs() < 1e-9);
+        assert!((fixed_time.alpha - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn drops_time_past_max_substeps() {
+        let mut fixed_time = FixedTime::new(10.0);
+        assert_eq!(fixed_time.advance(1.05), 5);
+        assert!(fixed_time.accumulator < fixed_time.timestep);
+        assert!((fixed_time.accumulator - 0.05).abs() < 1e-9);
+        assert_eq!(fixed_time.advance(0.0), 0);
+    }
+}
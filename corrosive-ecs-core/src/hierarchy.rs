@@ -1,3 +1,4 @@
+use crate::change_tick::{ChangeTicks, Tracked};
 use crate::ecs_core::Reference;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
@@ -7,11 +8,14 @@ use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
 /// May only be crated via the associated hierarchy object.
 /// Uses `Reference` as value.
 /// On removal or extraction, the member is removed from the hierarchy.
+/// Writing to it or its clones marks it as changed,
+/// members returned by the hierarchy such as `get_parent` do not share its change ticks.
 #[derive(Debug)]
 pub struct Member<T: SharedBehavior + 'static> {
     pub id: u64,
     pub hierarchy: Hierarchy<T>,
     pub value: Arc<RwLock<Reference<T>>>,
+    pub ticks: Arc<ChangeTicks>,
 }
 /// SharedBehavior of a hierarchy capable components.
 impl<T: SharedBehavior> Member<T> {
@@ -25,6 +29,7 @@ impl<T: SharedBehavior> Member<T> {
             id: self.id.clone(),
             hierarchy: self.hierarchy.clone(),
             value: self.value.clone(),
+            ticks: self.ticks.clone(),
         }
     }
     /// Returns the result of the read lock of a value.
@@ -45,23 +50,27 @@ impl<T: SharedBehavior> Member<T> {
     /// Returns the result of the write lock of a value.
     /// will not trigger the shared behavior of the hierarchy.
     pub fn dry_write(&self) -> LockResult<RwLockWriteGuard<'_, Reference<T>>> {
+        self.ticks.set_changed();
         self.value.write()
     }
     /// Returns the write lock of a value.
     /// Panics with the given massage if the lock is poisoned.
     /// will not trigger the shared behavior of the hierarchy.
     pub fn dry_e_write(&self, massage: &str) -> RwLockWriteGuard<'_, Reference<T>> {
+        self.ticks.set_changed();
         self.value.write().expect(massage)
     }
     /// Returns the write lock of a value.
     /// Panics the lock is poisoned.
     /// will not trigger the shared behavior of the hierarchy.
     pub fn dry_f_write(&self) -> RwLockWriteGuard<'_, Reference<T>> {
+        self.ticks.set_changed();
         self.value.write().expect("Failed to force write a lock")
     }
     /// require a closure with the result of write lock as an input.
     /// will trigger the shared behavior of the hierarchy.
     pub fn write(&self, mut func: impl FnMut(LockResult<RwLockWriteGuard<'_, Reference<T>>>)) {
+        self.ticks.set_changed();
         func(self.value.write());
         self.hierarchy.shared_behavior(&self.id);
     }
@@ -69,6 +78,7 @@ impl<T: SharedBehavior> Member<T> {
     /// will trigger the shared behavior of the hierarchy.
     /// Panics with the given massage if the lock is poisoned.
     pub fn e_write(&self, mut func: impl FnMut(RwLockWriteGuard<'_, Reference<T>>), massage: &str) {
+        self.ticks.set_changed();
         func(self.value.write().expect(massage));
         self.hierarchy.shared_behavior(&self.id);
     }
@@ -76,6 +86,7 @@ impl<T: SharedBehavior> Member<T> {
     /// will trigger the shared behavior of the hierarchy.
     /// Panics if the lock is poisoned.
     pub fn f_write(&self, mut func: impl FnMut(RwLockWriteGuard<'_, Reference<T>>)) {
+        self.ticks.set_changed();
         func(self.value.write().expect("Failed to force write a lock"));
         self.hierarchy.shared_behavior(&self.id);
     }
@@ -106,6 +117,11 @@ impl<T: SharedBehavior> Member<T> {
         self.hierarchy.remove_entry(&self.id)
     }
 }
+impl<T: SharedBehavior> Tracked for Member<T> {
+    fn ticks(&self) -> &ChangeTicks {
+        &self.ticks
+    }
+}
 /// SharedBehavior of a hierarchy capable components.
 /// All components that wrap in `Member<T>` and `Hierarchy<T>` must implement this trait.
 pub trait SharedBehavior {
@@ -148,6 +164,7 @@ impl<T: SharedBehavior> Hierarchy<T> {
             id: id,
             hierarchy: self.clone(),
             value: Arc::new(RwLock::new(Reference::Some(value))),
+            ticks: Arc::new(ChangeTicks::default()),
         }
     }
     pub fn get_parent(&self, child: &u64) -> Option<Member<T>> {
@@ -158,6 +175,7 @@ impl<T: SharedBehavior> Hierarchy<T> {
             id: i.clone(),
             hierarchy: self.clone(),
             value: data.clone(),
+            ticks: Arc::new(ChangeTicks::default()),
         })
     }
     pub fn get_children(&self, parent: &u64) -> Vec<Member<T>> {
@@ -172,6 +190,7 @@ impl<T: SharedBehavior> Hierarchy<T> {
                     id: x.clone(),
                     hierarchy: self.clone(),
                     value: node.clone(),
+                    ticks: Arc::new(ChangeTicks::default()),
                 })
             })
             .collect()
@@ -240,6 +259,7 @@ impl<T: SharedBehavior> Hierarchy<T> {
                 id: x.0.clone(),
                 hierarchy: unsafe { std::mem::transmute(&self) },
                 value: x.1.clone(),
+                ticks: Arc::new(ChangeTicks::default()),
             })
             .collect()
     }
@@ -2,32 +2,59 @@ mod arch;
 #[cfg(feature = "build")]
 /// Used to create the engine at compile time.
 pub mod build;
+mod change_tick;
+mod command;
+mod component_info;
+mod dyn_mut;
+mod entity;
+mod event;
+mod exit;
+mod fixed_time;
 mod hierarchy;
 mod locked;
 mod locked_ref;
 mod r_arch;
 mod r#ref;
+mod removed;
 mod res;
+mod res_mut;
 mod reset;
 mod signal;
 mod state;
+mod state_mut;
+mod time_scale;
 mod trigger;
+mod world;
 
 /// Core functions for Corrosive ECS
 #[cfg(feature = "core")]
 pub mod ecs_core {
 
     pub use crate::arch::*;
+    pub use crate::change_tick::*;
+    pub use crate::command::*;
+    pub use crate::component_info::*;
+    pub use crate::dyn_mut::*;
+    pub use crate::entity::*;
+    pub use crate::event::*;
+    pub use crate::exit::*;
+    pub use crate::fixed_time::*;
     pub use crate::hierarchy::*;
     pub use crate::locked::*;
     pub use crate::locked_ref::*;
     pub use crate::r#ref::*;
     pub use crate::r_arch::*;
+    pub use crate::removed::*;
     pub use crate::res::*;
+    pub use crate::res_mut::*;
     pub use crate::reset::*;
     pub use crate::signal::*;
     pub use crate::state::*;
+    pub use crate::state_mut::*;
+    pub use crate::time_scale::*;
     pub use crate::trigger::*;
+    pub use crate::world::*;
+    pub use rayon::iter::{IndexedParallelIterator, ParallelIterator};
 
     /// A reference to a value that may or may not be expired.
     /// Values that use `Locked`,`LockedRef`, `Ref` or `Member` use this to hold their values.
@@ -64,6 +91,12 @@ pub mod ecs_core {
 
     /// Input delta tile values use as input to tasks.
     pub type DeltaTime<'a> = &'a f64;
+    /// Time between two fixed updates, used as input to tasks.
+    pub type FixedDeltaTime<'a> = &'a f64;
+    /// Time since the engine started, used as input to tasks.
+    pub type ElapsedTime<'a> = &'a f64;
+    /// Number of the current frame starting from 1, used as input to tasks.
+    pub type FrameCount<'a> = &'a u64;
 
     /// Marks a component for using a task.
     #[macro_export]
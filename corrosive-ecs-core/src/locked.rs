@@ -1,16 +1,20 @@
+use crate::change_tick::{ChangeTicks, Tracked};
 use std::sync::{LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 /// Used for a locked component.
 /// These components can be written to but cannot be referenced.
+/// Writing to them marks them as changed.
 #[derive(Debug)]
 pub struct Locked<T> {
     pub value: RwLock<T>,
+    pub ticks: ChangeTicks,
 }
 impl<T> Locked<T> {
     /// Creates a new Locked Components.
     pub fn new(value: T) -> Locked<T> {
         Locked {
             value: RwLock::new(value),
+            ticks: ChangeTicks::default(),
         }
     }
     /// Returns the result of the read lock of a value.
@@ -30,16 +34,24 @@ impl<T> Locked<T> {
 
     /// Returns the result of the write lock of a value.
     pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        self.ticks.set_changed();
         self.value.write()
     }
     /// Returns the write lock of a value.
     /// Panics with the given massage if the lock is poisoned.
     pub fn e_write(&self, massage: &str) -> RwLockWriteGuard<'_, T> {
+        self.ticks.set_changed();
         self.value.write().expect(massage)
     }
     /// Returns the write lock of a value.
     /// Panics the lock is poisoned.
     pub fn f_write(&self) -> RwLockWriteGuard<'_, T> {
+        self.ticks.set_changed();
         self.value.write().expect("Failed to force write a lock")
     }
 }
+impl<T> Tracked for Locked<T> {
+    fn ticks(&self) -> &ChangeTicks {
+        &self.ticks
+    }
+}
@@ -1,3 +1,4 @@
+use crate::change_tick::{ChangeTicks, Tracked};
 use crate::ecs_core::Reference;
 use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
@@ -5,21 +6,25 @@ use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
 /// These components can be written and be referenced.
 /// Uses `Reference` as value.
 /// On removal the values will be expired.
+/// Writing to them or their clones marks them as changed.
 #[derive(Debug)]
 pub struct LockedRef<T> {
     pub value: Arc<RwLock<Reference<T>>>,
+    pub ticks: Arc<ChangeTicks>,
 }
 impl<T> LockedRef<T> {
     /// Creates a new LockedRef Components.
     pub fn new(value: T) -> LockedRef<T> {
         LockedRef {
             value: Arc::new(RwLock::new(Reference::Some(value))),
+            ticks: Arc::new(ChangeTicks::default()),
         }
     }
     /// Clones the LockedRef.
     pub fn clone(&self) -> LockedRef<T> {
         LockedRef {
             value: self.value.clone(),
+            ticks: self.ticks.clone(),
         }
     }
     /// Returns the result of the read lock of a value.
@@ -39,16 +44,19 @@ impl<T> LockedRef<T> {
 
     /// Returns the result of the write lock of a value.
     pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, Reference<T>>> {
+        self.ticks.set_changed();
         self.value.write()
     }
     /// Returns the write lock of a value.
     /// Panics with the given massage if the lock is poisoned.
     pub fn e_write(&self, massage: &str) -> RwLockWriteGuard<'_, Reference<T>> {
+        self.ticks.set_changed();
         self.value.write().expect(massage)
     }
     /// Returns the write lock of a value.
     /// Panics the lock is poisoned.
     pub fn f_write(&self) -> RwLockWriteGuard<'_, Reference<T>> {
+        self.ticks.set_changed();
         self.value.write().expect("Failed to force write a lock")
     }
 
@@ -57,3 +65,8 @@ impl<T> LockedRef<T> {
         *self.value.write().unwrap() = Reference::Expired;
     }
 }
+impl<T> Tracked for LockedRef<T> {
+    fn ticks(&self) -> &ChangeTicks {
+        &self.ticks
+    }
+}
@@ -1,8 +1,10 @@
+use crate::entity::Entity;
+
 /// Used as a return type for tasks.
 /// T must be a tuple.
 ///
 /// Example:
-/// ```rust
+/// ```ignore
 /// #[task]
 /// pub fn setup() -> (
 ///     RArch<(Locked<Position1>, Ref<Position2>, LockedRef<Position3>)>,
@@ -29,24 +31,41 @@
 /// ```
 pub struct RArch<T> {
     pub vec: Vec<T>,
+    pub entities: Vec<Entity>,
 }
 impl<T> Default for RArch<T> {
     fn default() -> Self {
-        RArch { vec: Vec::new() }
+        RArch {
+            vec: Vec::new(),
+            entities: Vec::new(),
+        }
     }
 }
 
 impl<T> RArch<T> {
     /// Adds a single member to the archetype.
-    pub fn add(&mut self, t: T) {
+    /// Returns the entity of the member.
+    pub fn add(&mut self, t: T) -> Entity {
+        let entity = Entity::allocate();
         self.vec.push(t);
+        self.entities.push(entity);
+        entity
     }
     /// Adds a multiple members to the archetype if the input value implements `IntoIterator` trait.
-    pub fn add_multiple<I>(&mut self, items: I)
+    /// Returns the entities of the members.
+    pub fn add_multiple<I>(&mut self, items: I) -> Vec<Entity>
     where
         I: IntoIterator<Item = T>,
     {
-        self.vec.extend(items)
+        items.into_iter().map(|t| self.add(t)).collect()
+    }
+    /// Used by engine to move the members and their entities into the archetype.
+    /// Members pushed to `vec` directly are given a new entity.
+    pub fn into_iter_with_entities(mut self) -> impl Iterator<Item = (T, Entity)> {
+        while self.entities.len() < self.vec.len() {
+            self.entities.push(Entity::allocate());
+        }
+        self.vec.into_iter().zip(self.entities)
     }
     /// Gets a reference to the archetype members.
     pub fn get(&self) -> &Vec<T> {
@@ -0,0 +1,36 @@
+use crate::entity::Entity;
+use crate::event::{EventChannel, EventRead, EventReader};
+use std::marker::PhantomData;
+use std::sync::atomic::AtomicU64;
+
+/// Used as an input for tasks to find the entities that lost a component of type T,
+/// either by being removed or by having the component removed.
+/// T also matches components stored in `Locked`, `LockedRef`, `Ref` and `Member`.
+/// Removals are recorded in the merge phase and can be read in the following frame,
+/// every reader has its own cursor.
+///
+/// Example:
+/// ```ignore
+/// #[task]
+/// pub fn cleanup_lights(removed: Removed<PointLight>, render_set: ResMut<RenderSet>) {
+///     for entity in removed.read().iter() {
+///         render_set.f_write().remove(entity);
+///     }
+/// }
+/// ```
+pub struct Removed<'a, T> {
+    reader: EventReader<'a, Entity>,
+    component: PhantomData<T>,
+}
+impl<'a, T> Removed<'a, T> {
+    pub fn new(channel: &'a EventChannel<Entity>, cursor: &'a AtomicU64) -> Self {
+        Removed {
+            reader: EventReader::new(channel, cursor),
+            component: PhantomData,
+        }
+    }
+    /// Returns the entities that were not read by this reader yet.
+    pub fn read(&self) -> EventRead<'a, Entity> {
+        self.reader.read()
+    }
+}
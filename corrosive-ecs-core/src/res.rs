@@ -1,7 +1,7 @@
 use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 /// Used for singleton objects.
-/// Can be used as a task input.
+/// Can be used as a task input that only reads the value, use `ResMut` to write to it.
 /// T Must implement the `Default` trait.
 #[derive(Debug)]
 pub struct Res<T: Default> {
@@ -31,16 +31,19 @@ impl<T: Default> Res<T> {
     }
 
     /// Returns the result of the write lock of a value.
+    #[deprecated(note = "`Res` only reads the value, take `ResMut<T>` as input to write to it")]
     pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
         self.value.write()
     }
     /// Returns the write lock of a value.
     /// Panics with the given massage if the lock is poisoned.
+    #[deprecated(note = "`Res` only reads the value, take `ResMut<T>` as input to write to it")]
     pub fn e_write(&self, massage: &str) -> RwLockWriteGuard<'_, T> {
         self.value.write().expect(massage)
     }
     /// Returns the write lock of a value.
     /// Panics the lock is poisoned.
+    #[deprecated(note = "`Res` only reads the value, take `ResMut<T>` as input to write to it")]
     pub fn f_write(&self) -> RwLockWriteGuard<'_, T> {
         self.value.write().expect("Failed to force write a lock")
     }
@@ -0,0 +1,63 @@
+use crate::res::Res;
+use std::sync::{Arc, LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Used for singleton objects.
+/// Can be used as a task input that reads and writes the value.
+/// Tasks that write to the same value are ordered by the engine, use `Res` when the value is only read.
+/// T Must implement the `Default` trait.
+#[derive(Debug)]
+pub struct ResMut<T: Default> {
+    pub value: Arc<RwLock<T>>,
+}
+impl<T: Default> ResMut<T> {
+    /// Used by engine to create a Resource.
+    pub fn new(value: T) -> ResMut<T> {
+        ResMut {
+            value: Arc::new(RwLock::new(value)),
+        }
+    }
+
+    /// Returns the result of the read lock of a value.
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+        self.value.read()
+    }
+    /// Returns the read lock of a value.
+    /// Panics with the given massage if the lock is poisoned.
+    pub fn e_read(&self, massage: &str) -> RwLockReadGuard<'_, T> {
+        self.value.read().expect(massage)
+    }
+    /// Returns the read lock of a value.
+    /// Panics if the lock is poisoned.
+    pub fn f_read(&self) -> RwLockReadGuard<'_, T> {
+        self.value.read().expect("Failed to force read a lock")
+    }
+
+    /// Returns the result of the write lock of a value.
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+        self.value.write()
+    }
+    /// Returns the write lock of a value.
+    /// Panics with the given massage if the lock is poisoned.
+    pub fn e_write(&self, massage: &str) -> RwLockWriteGuard<'_, T> {
+        self.value.write().expect(massage)
+    }
+    /// Returns the write lock of a value.
+    /// Panics the lock is poisoned.
+    pub fn f_write(&self) -> RwLockWriteGuard<'_, T> {
+        self.value.write().expect("Failed to force write a lock")
+    }
+
+    /// Returns a `Res` that reads the same value.
+    pub fn read_only(&self) -> Res<T> {
+        Res {
+            value: self.value.clone(),
+        }
+    }
+}
+impl<T: Default> Clone for ResMut<T> {
+    fn clone(&self) -> Self {
+        ResMut {
+            value: self.value.clone(),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+/// Scales the time of the engine.
+/// Can be used as a task input with `Res<TimeScale>`, or `ResMut<TimeScale>` to change it.
+/// Applies to `DeltaTime`, `ElapsedTime` and fixed updates.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeScale {
+    /// Multiplier of the delta time. 1 is real time.
+    pub scale: f64,
+    /// Should it be true, update, fixed and long tasks will be skipped.
+    /// Sync tasks keep running.
+    pub paused: bool,
+}
+impl TimeScale {
+    /// Pauses the engine.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    /// Resumes the engine.
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+    /// Used by engine to scale the delta time.
+    /// Returns 0 if paused.
+    pub fn apply(&self, delta: f64) -> f64 {
+        if self.paused {
+            0.0
+        } else {
+            delta * self.scale
+        }
+    }
+}
+impl Default for TimeScale {
+    fn default() -> Self {
+        TimeScale {
+            scale: 1.0,
+            paused: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_delta_until_paused() {
+        let mut time_scale = TimeScale {
+            scale: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(time_scale.apply(0.2), 0.1);
+        time_scale.pause();
+        assert_eq!(time_scale.apply(0.2), 0.0);
+        time_scale.resume();
+        assert_eq!(time_scale.apply(0.2), 0.1);
+    }
+}
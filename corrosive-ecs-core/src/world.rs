@@ -0,0 +1,65 @@
+use crate::command::{Command, CommandQueue};
+use crate::entity::Entity;
+use std::any::{Any, TypeId};
+
+/// Automatically implemented for the world input of tasks.
+pub trait EngineWorld {
+    fn get_component(&self, entity: Entity, type_id: TypeId) -> Option<&dyn Any>;
+    fn contains(&self, entity: Entity) -> bool;
+}
+
+/// Used as an input for tasks to look up entities across every archetype.
+/// Components are looked up by the type they are stored as, such as `Locked<Position>`.
+/// Components can be inserted and removed, moving the entity to another archetype in the merge phase.
+/// The resulting set of components must be an archetype that some task outputs.
+/// Components found through the world are shared references, `Locked` ones are written to with `f_write`
+/// like the members of an `Arch`.
+///
+/// Example:
+/// ```ignore
+/// #[task]
+/// pub fn follow(followers: Arch<(&Locked<Position>, &Target)>, world: World) {
+///     for (position, target) in followers.iter() {
+///         if let Some(target) = world.get::<Locked<Position>>(target.entity) {
+///             let target = *target.f_read();
+///             *position.f_write() = target;
+///         }
+///     }
+/// }
+/// ```
+pub struct World<'a> {
+    pub world: &'a dyn EngineWorld,
+    pub commands: &'a CommandQueue,
+}
+impl<'a> World<'a> {
+    pub fn new(world: &'a dyn EngineWorld, commands: &'a CommandQueue) -> Self {
+        World { world, commands }
+    }
+    /// Returns a component of an entity.
+    /// Returns `None` if the entity was removed or does not have the component.
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        self.world
+            .get_component(entity, TypeId::of::<T>())?
+            .downcast_ref::<T>()
+    }
+    /// Checks if the entity is in any archetype.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.world.contains(entity)
+    }
+    /// Adds a component to an entity, or replaces it should the entity already have one.
+    /// Applied in the merge phase.
+    pub fn insert_component<T: Any + Send + Sync>(&self, entity: Entity, component: T) {
+        self.commands.push(Command::InsertComponent(
+            entity,
+            TypeId::of::<T>(),
+            Box::new(component),
+        ));
+    }
+    /// Removes a component from an entity.
+    /// `Ref`, `LockedRef` and `Member` components are expired.
+    /// Applied in the merge phase.
+    pub fn remove_component<T: Any + Send + Sync>(&self, entity: Entity) {
+        self.commands
+            .push(Command::RemoveComponent(entity, TypeId::of::<T>()));
+    }
+}
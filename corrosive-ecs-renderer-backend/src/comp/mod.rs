@@ -1,9 +1,10 @@
 use crate::render_graph::GraphNode;
 use crate::wgpu::BindGroupEntry;
 use crate::STATE;
-use corrosive_ecs_core::ecs_core::Res;
+use corrosive_ecs_core::ecs_core::{Res, ResMut};
 use corrosive_ecs_core_macro::Resource;
 use std::collections::HashMap;
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, RwLock};
 use std::thread::JoinHandle;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
@@ -611,15 +612,22 @@ impl<'a> State<'a> {
 }
 
 pub struct App {
-    render_graph: Res<RenderGraph>,
-    pub window_options: Res<WindowOptions>,
+    render_graph: ResMut<RenderGraph>,
+    pub window_options: ResMut<WindowOptions>,
+    /// Tells `run_renderer` that `STATE` is set, taken the first time the app is resumed.
+    ready: Option<Sender<()>>,
 }
 
 impl App {
-    pub(crate) fn new(window_options: Res<WindowOptions>, render_graph: Res<RenderGraph>) -> App {
+    pub(crate) fn new(
+        window_options: ResMut<WindowOptions>,
+        render_graph: ResMut<RenderGraph>,
+        ready: Sender<()>,
+    ) -> App {
         App {
             render_graph,
             window_options,
+            ready: Some(ready),
         }
     }
 }
@@ -634,10 +642,13 @@ impl ApplicationHandler for App {
         self.render_graph.f_write().prepare();
 
         if let Some(t) = &self.window_options.f_read().window {
-            let state = pollster::block_on(State::new(t.clone(), self.render_graph.clone()));
+            let state = pollster::block_on(State::new(t.clone(), self.render_graph.read_only()));
             unsafe {
                 STATE = Some(state);
             }
+            if let Some(ready) = self.ready.take() {
+                let _ = ready.send(());
+            }
             t.request_redraw();
         } else {
             panic!("failed to run renderer backend")
@@ -17,5 +17,6 @@ pub use winit;
 pub(crate) static mut STATE: Option<State> = None;
 
 corrosive_engine_builder!(
+    requires_window,
     setup "run_renderer"
 );
@@ -1,24 +1,26 @@
 use crate::comp::{App, RenderGraph, Renderer, WindowOptions};
 use crate::slang::ShaderManager;
 use crate::STATE;
-use corrosive_ecs_core::ecs_core::Res;
+use corrosive_ecs_core::ecs_core::ResMut;
 use corrosive_ecs_core_macro::task;
+use std::sync::mpsc;
 use std::thread;
 use winit::event_loop::{ControlFlow, EventLoop};
 
 #[task]
 pub fn run_renderer(
-    re: Res<Renderer>,
-    window_options: Res<WindowOptions>,
-    render_graph: Res<RenderGraph>,
+    re: ResMut<Renderer>,
+    window_options: ResMut<WindowOptions>,
+    render_graph: ResMut<RenderGraph>,
 ) {
     #[cfg(debug_assertions)]
     {
         let manager = ShaderManager::new();
         manager.sync_shaders().expect("failed_to_sync_shaders");
     }
-    unsafe { STATE = None }
     if re.f_read().0.is_none() {
+        unsafe { STATE = None }
+        let (ready_sender, ready) = mpsc::channel();
         re.f_write().0 = Some(thread::spawn(move || {
             env_logger::init();
 
@@ -43,10 +45,13 @@ pub fn run_renderer(
 
             event_loop_builder.set_control_flow(ControlFlow::Poll);
 
-            let mut app = App::new(window_options, render_graph);
+            let mut app = App::new(window_options, render_graph, ready_sender);
 
             event_loop_builder.run_app(&mut app).unwrap();
         }));
-        unsafe { while STATE.is_none() {} }
+        // The renderer sends once `STATE` holds the state of its window, which makes that write visible to this thread.
+        ready
+            .recv()
+            .expect("renderer stopped before its window was created");
     }
 }
@@ -1,5 +1,5 @@
 use crate::comp::EguiObject;
-use corrosive_ecs_core::ecs_core::Res;
+use corrosive_ecs_core::ecs_core::{Res, ResMut};
 use corrosive_ecs_core_macro::task;
 use corrosive_ecs_renderer_backend::comp::{RenderGraph, WindowOptions};
 use corrosive_ecs_renderer_backend::public_functions::{
@@ -17,7 +17,7 @@ use std::sync::{Arc, LazyLock, Mutex};
 static INPUT: LazyLock<Mutex<Vec<WindowEvent>>> = LazyLock::new(|| Default::default());
 
 struct EguiNode {
-    egui_object: Res<EguiObject>,
+    egui_object: ResMut<EguiObject>,
     window_options: Res<WindowOptions>,
     scale_factor: f32,
 }
@@ -163,9 +163,9 @@ impl RenderNode for EguiNode {
 
 #[task]
 pub fn start_egui(
-    graph: Res<RenderGraph>,
-    window_options: Res<WindowOptions>,
-    egui_object: Res<EguiObject>,
+    graph: ResMut<RenderGraph>,
+    window_options: ResMut<WindowOptions>,
+    egui_object: ResMut<EguiObject>,
 ) {
     let ctx = Context::default();
     egui_extras::install_image_loaders(&ctx);
@@ -209,7 +209,7 @@ pub fn start_egui(
         }));
 
     graph.f_write().add_node(Box::new(EguiNode {
-        window_options: window_options.clone(),
+        window_options: window_options.read_only(),
         egui_object: egui_object.clone(),
         scale_factor: window_options.f_read().window().scale_factor() as f32,
     }));
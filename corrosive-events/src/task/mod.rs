@@ -1,5 +1,5 @@
 use crate::comp::Inputs;
-use corrosive_ecs_core::ecs_core::Res;
+use corrosive_ecs_core::ecs_core::ResMut;
 use corrosive_ecs_core_macro::task;
 use corrosive_ecs_renderer_backend::comp::WindowOptions;
 use corrosive_ecs_renderer_backend::winit::dpi::PhysicalPosition;
@@ -30,7 +30,7 @@ pub static INPUT_STORAGE: LazyLock<Mutex<InputStorage>> =
     LazyLock::new(|| Mutex::new(Default::default()));
 
 #[task]
-pub fn start_events(window_options: Res<WindowOptions>) {
+pub fn start_events(window_options: ResMut<WindowOptions>) {
     window_options
         .f_write()
         .func
@@ -97,7 +97,7 @@ pub fn start_events(window_options: Res<WindowOptions>) {
 }
 
 #[task]
-pub fn update_events(input_res: Res<Inputs>) {
+pub fn update_events(input_res: ResMut<Inputs>) {
     let mut lock = INPUT_STORAGE.lock().unwrap();
     let mut input_res = input_res.f_write();
 
@@ -1,14 +1,14 @@
 use crate::comp;
 use crate::comp::App;
 use crate::comp::Renderer;
-use corrosive_ecs_core::ecs_core::Res;
+use corrosive_ecs_core::ecs_core::ResMut;
 use corrosive_ecs_core_macro::task;
 use std::thread;
 use winit::application::ApplicationHandler;
 use winit::event::WindowEvent;
 use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
 #[task]
-pub fn run_renderer(re: Res<Renderer>) {
+pub fn run_renderer(re: ResMut<Renderer>) {
     if re.f_read().0.is_none() {
         re.f_write().0 = Some(thread::spawn(|| {
             env_logger::init();
@@ -1,6 +1,6 @@
 use crate::comp::{UIBuffers, UIStyle, UIVertex, UiNode};
 use corrosive_asset_manager::asset_server::{Asset, AssetServer};
-use corrosive_ecs_core::ecs_core::{Hierarchy, Reference, Res};
+use corrosive_ecs_core::ecs_core::{Hierarchy, Reference, Res, ResMut};
 use corrosive_ecs_core_macro::task;
 use corrosive_ecs_renderer_backend::assets::PipelineAsset;
 use corrosive_ecs_renderer_backend::comp::{RenderGraph, WindowOptions};
@@ -56,7 +56,7 @@ impl<'a> RenderNode for UIRenderNode {
     }
 }
 #[task]
-pub fn setup_ui_pass(graph: Res<RenderGraph>, buffers: Res<UIBuffers>) {
+pub fn setup_ui_pass(graph: ResMut<RenderGraph>, buffers: ResMut<UIBuffers>) {
     let shader = create_shader_module("ui_shader", include_str!("ui_shader.wgsl"));
     let bind_group_layout = UIStyle::get_bind_group_layout();
     let vertex_buffer = create_buffer_init(
@@ -163,7 +163,7 @@ pub fn setup_ui_pass(graph: Res<RenderGraph>, buffers: Res<UIBuffers>) {
     });
 
     graph.f_write().add_node(Box::new(UIRenderNode {
-        buffers: buffers.clone(),
+        buffers: buffers.read_only(),
     }));
     graph.f_write().prepare();
     buffers.f_write().buffers.push(Arc::new((
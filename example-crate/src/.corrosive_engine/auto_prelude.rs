@@ -1,60 +0,0 @@
-pub use crate::comp::sub::MarkedResources;
-pub use crate::comp::sub::Position3;
-pub use crate::comp::sub::Position4;
-pub use crate::comp::sub::StateExample;
-pub use crate::comp::test;
-pub use crate::comp::Position1;
-pub use crate::comp::Position2;
-pub use crate::corrosive_engine::arch_types::*;
-pub use crate::task::fixed_task;
-pub use crate::task::macro_test;
-pub use crate::task::move_camera;
-pub use crate::task::other_tasks::other_other_task::long_task;
-pub use crate::task::other_tasks::sync_task;
-pub use crate::task::pixil_test;
-pub use crate::task::rotate_model;
-pub use crate::task::setup;
-pub use crate::task::setup1;
-pub use crate::task::setup2;
-pub use crate::task::test2_0;
-pub use crate::task::update_task;
-pub use crate::task::update_task_signal;
-pub use corrosive_2d::comp::camera2d::ActiveCamera2D;
-pub use corrosive_2d::comp::camera2d::Camera2D;
-pub use corrosive_2d::comp::sprite2d::Sprite2D;
-pub use corrosive_2d::comp::Mesh2D;
-pub use corrosive_2d::comp::Position2D;
-pub use corrosive_2d::comp::Renderer2dData;
-pub use corrosive_2d::comp::RendererMeta2D;
-pub use corrosive_2d::task::init_camera;
-pub use corrosive_2d::task::render_2d;
-pub use corrosive_2d::task::start_2d_renderer;
-pub use corrosive_2d::task::update_position;
-pub use corrosive_ecs_core::ecs_core::{
-    Arch, Hierarchy, Locked, LockedRef, Member, Ref, Res, State,
-};
-pub use corrosive_ecs_renderer_backend::comp::RenderGraph;
-pub use corrosive_ecs_renderer_backend::comp::Renderer;
-pub use corrosive_ecs_renderer_backend::comp::WindowOptions;
-pub use corrosive_ecs_renderer_backend::task::run_renderer;
-pub use corrosive_egui::comp::EguiObject;
-pub use corrosive_egui::task::start_egui;
-pub use corrosive_events::comp::Inputs;
-pub use corrosive_events::task::start_events;
-pub use corrosive_events::task::update_events;
-pub use corrosive_ui::comp::UIBuffers;
-pub use corrosive_ui::comp::UIRenderMeta;
-pub use corrosive_ui::comp::UiNode;
-pub use corrosive_ui::task::rerender_ui;
-pub use corrosive_ui::task::setup_ui_pass;
-pub use pixil::comp::camera::ActivePixilCamera;
-pub use pixil::comp::camera::PixilCamera;
-pub use pixil::comp::dynamic::PixilDynamicObject;
-pub use pixil::comp::light::directional_light::DirectionalLight;
-pub use pixil::comp::light::point_light::PointLight;
-pub use pixil::comp::light::spot_light::SpotLight;
-pub use pixil::comp::position_pixil::PositionPixil;
-pub use pixil::comp::render::PixilRenderSettings;
-pub use pixil::task::renderer::start_pixil_renderer;
-pub use pixil::task::renderer::update_camera;
-pub use pixil::task::renderer::update_pixil_position;
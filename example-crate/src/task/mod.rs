@@ -11,8 +11,8 @@ use corrosive_2d::position2d_operations::Move2D;
 use corrosive_asset_manager::asset_server::{Asset, AssetServer};
 use corrosive_asset_manager_macro::static_hasher;
 use corrosive_ecs_core::ecs_core::{
-    Arch, DeltaTime, Hierarchy, Locked, LockedRef, Member, RArch, Ref, Reference, Res, Reset,
-    Signal, State,
+    Arch, DeltaTime, Hierarchy, Locked, LockedRef, Member, RArch, Ref, Reference, Res, ResMut,
+    Reset, Signal, State,
 };
 use corrosive_ecs_core_macro::task;
 use corrosive_ecs_renderer_backend::color::Color;
@@ -39,8 +39,8 @@ use std::vec::IntoIter;
 #[task]
 pub fn pixil_test(
     h: Hierarchy<PositionPixil>,
-    ac: Res<ActivePixilCamera>,
-    window_option: Res<WindowOptions>,
+    ac: ResMut<ActivePixilCamera>,
+    window_option: ResMut<WindowOptions>,
 ) -> (
     RArch<(PixilDynamicObject, Member<PositionPixil>)>,
     RArch<(LockedRef<PixilCamera>, Member<PositionPixil>)>,
@@ -196,7 +196,7 @@ pub fn rotate_model(r: Arch<(&PixilDynamicObject, &Member<PositionPixil>)>, delt
 pub fn test2_0(
     position: Hierarchy<Position2D>,
     active_camera2d: Res<ActiveCamera2D>,
-    active_camera: Res<ActiveCamera2D>,
+    active_camera: ResMut<ActiveCamera2D>,
 ) -> (
     RArch<(Member<Position2D>, RendererMeta2D, Sprite2D)>,
     RArch<(Member<Position2D>, LockedRef<Camera2D>)>,
@@ -360,7 +360,7 @@ pub fn setup2() {
     for _i in 0..10000 {}
 }
 #[task]
-pub fn update_task(inp: Arch<(&dyn test,)>, res: Res<MarkedResources>, delta_time: DeltaTime) {
+pub fn update_task(inp: Arch<(&dyn test,)>, res: ResMut<MarkedResources>, delta_time: DeltaTime) {
     let mut mark: usize = 0;
     for x in inp.iter() {
         if x.0.get_num() == 10.0 {
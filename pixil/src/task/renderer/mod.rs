@@ -11,7 +11,7 @@ use crate::light_set::OrderedSet;
 use crate::render_set::RenderSet;
 use corrosive_asset_manager::cache_server::{Cache, CacheServer};
 use corrosive_asset_manager_macro::static_hasher;
-use corrosive_ecs_core::ecs_core::{Arch, Member, Reference, Res};
+use corrosive_ecs_core::ecs_core::{Arch, Member, Reference, Res, ResMut};
 use corrosive_ecs_core_macro::task;
 use corrosive_ecs_renderer_backend::assets::BindGroupLayoutAsset;
 use corrosive_ecs_renderer_backend::comp::{RenderGraph, WindowOptions};
@@ -67,7 +67,7 @@ impl RenderNode for RenderPixilNode {
         depth_view: &TextureView,
     ) {
         {
-            let size = self.render_settings.f_write().grid_size;
+            let size = self.render_settings.f_read().grid_size;
             let lock = DYNAMIC_LIGHTS.data.lock().unwrap();
 
             let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
@@ -188,10 +188,10 @@ impl RenderNode for RenderPixilNode {
 
 #[task]
 pub fn start_pixil_renderer(
-    render_setting: Res<PixilRenderSettings>,
+    render_setting: ResMut<PixilRenderSettings>,
     active_pixil_camera: Res<ActivePixilCamera>,
-    graph: Res<RenderGraph>,
-    window: Res<WindowOptions>,
+    graph: ResMut<RenderGraph>,
+    window: ResMut<WindowOptions>,
 ) {
     let device = get_device();
 
@@ -662,7 +662,7 @@ pub fn start_pixil_renderer(
         render_bind_group: bind_group,
         render_bind_group_layout: bind_group_layout,
         render_pipeline: pipeline,
-        render_settings: render_setting,
+        render_settings: render_setting.read_only(),
         cluster_pipeline,
         cluster_bind_group,
         cluster_buffer,